@@ -0,0 +1,173 @@
+//! Capturas (PNG/PPM) y exportadores de malla (STL/PLY).
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::mesh::{Mesh, Vec3};
+use crate::render::{pack_rgb, Frame};
+
+/* ==== Capturas ==== */
+// Desempaqueta 0x00RRGGBB a RGB8 y codifica PNG
+pub fn save_png(color: &[u32], w: usize, h: usize, path: &Path) -> Result<(), String> {
+    let rgb: Vec<u8> = color.iter()
+        .flat_map(|&c| [(c >> 16) as u8, (c >> 8) as u8, c as u8])
+        .collect();
+    let img = image::RgbImage::from_raw(w as u32, h as u32, rgb)
+        .ok_or_else(|| "Buffer de tamaño incorrecto".to_string())?;
+    img.save(path).map_err(|e| format!("No se pudo guardar {}: {e}", path.display()))
+}
+
+// PPM binario (P6): cabecera de texto y RGB8 tal cual, sin crates de por medio
+pub fn write_ppm(frame: &Frame, path: &Path) -> Result<(), String> {
+    let err = |e: std::io::Error| format!("No se pudo escribir {}: {e}", path.display());
+    let mut w = BufWriter::new(File::create(path).map_err(err)?);
+    write!(w, "P6\n{} {}\n255\n", frame.w, frame.h).map_err(err)?;
+    for &c in &frame.color {
+        w.write_all(&[(c >> 16) as u8, (c >> 8) as u8, c as u8]).map_err(err)?;
+    }
+    w.flush().map_err(err)
+}
+
+// Lee un PPM P6 de 8 bits (como los de write_ppm) a (ancho, alto, 0x00RRGGBB)
+pub fn read_ppm(path: &Path) -> Result<(usize, usize, Vec<u32>), String> {
+    let data = std::fs::read(path).map_err(|e| format!("No se pudo leer {}: {e}", path.display()))?;
+    let bad = || format!("{}: no es un PPM P6 de 8 bits", path.display());
+    // cabecera: 4 campos separados por espacios, con posibles comentarios `#`
+    let (mut fields, mut i) = (Vec::new(), 0);
+    while fields.len() < 4 {
+        while data.get(i).is_some_and(|c| c.is_ascii_whitespace()) { i += 1; }
+        if data.get(i) == Some(&b'#') {
+            while data.get(i).is_some_and(|&c| c != b'\n') { i += 1; }
+            continue;
+        }
+        let start = i;
+        while data.get(i).is_some_and(|c| !c.is_ascii_whitespace()) { i += 1; }
+        if start == i { return Err(bad()); }
+        fields.push(std::str::from_utf8(&data[start..i]).map_err(|_| bad())?);
+    }
+    let num = |s: &str| s.parse::<usize>().map_err(|_| bad());
+    if fields[0] != "P6" || num(fields[3])? != 255 { return Err(bad()); }
+    let (w, h) = (num(fields[1])?, num(fields[2])?);
+    let pixels = data.get(i + 1..i + 1 + 3*w*h).ok_or_else(bad)?; // un espacio tras el 255
+    let color = pixels.chunks_exact(3)
+        .map(|p| ((p[0] as u32) << 16) | ((p[1] as u32) << 8) | p[2] as u32)
+        .collect();
+    Ok((w, h, color))
+}
+
+// Píxeles en los que algún canal difiere más que `tolerance`
+pub fn count_diff_pixels(a: &[u32], b: &[u32], tolerance: u8) -> usize {
+    let channel = |c: u32, shift: u32| ((c >> shift) & 0xFF) as i32;
+    a.iter().zip(b)
+        .filter(|&(&x, &y)| [16, 8, 0].iter().any(|&s| (channel(x, s) - channel(y, s)).abs() > tolerance as i32))
+        .count()
+}
+
+/* ==== Exportadores ==== */
+// Elige el formato por extensión: .ply -> PLY ascii, resto -> STL binario
+pub fn export_mesh(mesh: &Mesh, path: &Path) -> Result<(), String> {
+    let is_ply = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ply"));
+    if is_ply { export_ply(mesh, path) } else { export_stl(mesh, path) }
+}
+
+// STL binario: cabecera de 80 bytes, nº de triángulos y por cada uno normal + 3 vértices
+// (f32 little-endian) + 2 bytes de atributos. Los triángulos de área cero se omiten.
+pub fn export_stl(mesh: &Mesh, path: &Path) -> Result<(), String> {
+    let tris: Vec<(Vec3, [Vec3;3])> = mesh.indices.chunks_exact(3)
+        .filter_map(|t| {
+            let p = [t[0].0, t[1].0, t[2].0].map(|i| mesh.positions[i as usize]);
+            let n = (p[1] - p[0]).cross(p[2] - p[0]);
+            let len = n.length();
+            (len > 0.0).then(|| (Vec3(n.0/len, n.1/len, n.2/len), p))
+        })
+        .collect();
+
+    let err = |e: std::io::Error| format!("No se pudo escribir {}: {e}", path.display());
+    let mut w = BufWriter::new(File::create(path).map_err(err)?);
+    let mut header = [0u8; 80];
+    let tag = b"lector_obj";
+    header[..tag.len()].copy_from_slice(tag);
+    w.write_all(&header).map_err(err)?;
+    w.write_all(&(tris.len() as u32).to_le_bytes()).map_err(err)?;
+    for (n, p) in &tris {
+        for Vec3(x, y, z) in std::iter::once(n).chain(p) {
+            for c in [x, y, z] { w.write_all(&c.to_le_bytes()).map_err(err)?; }
+        }
+        w.write_all(&[0, 0]).map_err(err)?;
+    }
+    w.flush().map_err(err)
+}
+
+// PLY ascii conservando vértices compartidos. Sin normales los vértices son las
+// posiciones tal cual; con normales se parte cada posición por (v, vn) distinto.
+// La cabecera declara solo las propiedades presentes (normales, colores).
+pub fn export_ply(mesh: &Mesh, path: &Path) -> Result<(), String> {
+    let has_normals = !mesh.normals.is_empty();
+    let has_colors = !mesh.colors.is_empty();
+
+    let (verts, faces): (Vec<(u32, Option<u32>)>, Vec<u32>) = if has_normals {
+        let mut verts = Vec::new();
+        let mut seen: HashMap<(u32, Option<u32>), u32> = HashMap::new();
+        let faces = mesh.indices.iter().map(|&(v, _, vn)| {
+            *seen.entry((v, vn)).or_insert_with(|| { verts.push((v, vn)); (verts.len() - 1) as u32 })
+        }).collect();
+        (verts, faces)
+    } else {
+        ((0..mesh.positions.len() as u32).map(|v| (v, None)).collect(), mesh.indices.iter().map(|c| c.0).collect())
+    };
+
+    let err = |e: std::io::Error| format!("No se pudo escribir {}: {e}", path.display());
+    let mut w = BufWriter::new(File::create(path).map_err(err)?);
+    let mut header = format!("ply\nformat ascii 1.0\ncomment lector_obj\nelement vertex {}\n", verts.len());
+    header += "property float x\nproperty float y\nproperty float z\n";
+    if has_normals { header += "property float nx\nproperty float ny\nproperty float nz\n"; }
+    if has_colors  { header += "property uchar red\nproperty uchar green\nproperty uchar blue\n"; }
+    header += &format!("element face {}\nproperty list uchar int vertex_indices\nend_header\n", faces.len() / 3);
+    w.write_all(header.as_bytes()).map_err(err)?;
+
+    for &(v, vn) in &verts {
+        let Vec3(x, y, z) = mesh.positions[v as usize];
+        let mut line = format!("{x} {y} {z}");
+        if has_normals {
+            let Vec3(nx, ny, nz) = vn.map_or(Vec3(0.0, 0.0, 0.0), |n| mesh.normals[n as usize]);
+            line += &format!(" {nx} {ny} {nz}");
+        }
+        if has_colors {
+            let rgb = pack_rgb(mesh.colors[v as usize]);
+            line += &format!(" {} {} {}", (rgb >> 16) & 0xFF, (rgb >> 8) & 0xFF, rgb & 0xFF);
+        }
+        writeln!(w, "{line}").map_err(err)?;
+    }
+    for f in faces.chunks_exact(3) {
+        writeln!(w, "3 {} {} {}", f[0], f[1], f[2]).map_err(err)?;
+    }
+    w.flush().map_err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{parse_obj, LoadOptions};
+    use std::io::Cursor;
+
+    #[test]
+    fn stl_cuenta_los_triangulos_no_degenerados() {
+        // un quad (2 triángulos) y una cara con los vértices alineados, que se omite
+        let src = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nf 1 2 3 4\nf 1 2 5\n";
+        let mesh = parse_obj(Cursor::new(src), &LoadOptions::default()).unwrap();
+        assert_eq!(mesh.indices.len(), 9);
+
+        let dir = std::env::temp_dir().join(format!("lector_obj_stl_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("quad.stl");
+        export_stl(&mesh, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let count = u32::from_le_bytes(bytes[80..84].try_into().unwrap());
+        assert_eq!(count, 2);
+        assert_eq!(bytes.len(), 84 + 50 * count as usize);
+    }
+}
@@ -0,0 +1,7 @@
+//! Visor de OBJ por software: lectura, proyección y raster en CPU.
+//! El binario (`main.rs`) solo pone la ventana, la línea de comandos y el bucle.
+
+pub mod export;
+pub mod math;
+pub mod mesh;
+pub mod render;
@@ -1,325 +1,860 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
 
-const WIDTH: usize = 800;
-const HEIGHT: usize = 600;
+use lector_obj::export::{count_diff_pixels, export_mesh, export_stl, read_ppm, save_png, write_ppm};
+use lector_obj::math::{aspect, center_and_scale_to_unit, scene_bounds};
+use lector_obj::mesh::{load_obj, LoadOptions, Mesh, Vec3};
+use lector_obj::render::{
+    draw_axis_gizmo, draw_hud, fit_scene, render_into, Camera, Frame, RenderOptions, Scene, SceneStyle, StageTimes,
+    Texture, DEFAULT_BG, DEFAULT_FIT_MARGIN,
+};
 
-/* ==== Estructuras básicas ==== */
-#[derive(Clone, Copy, Debug)]
-pub struct Vec3(pub f32, pub f32, pub f32);
-#[derive(Clone, Copy, Debug)]
-pub struct Vec2(pub f32, pub f32);
-
-#[derive(Debug, Clone)]
-pub struct Mesh {
-    pub positions: Vec<Vec3>,
-    pub texcoords: Vec<Vec2>,
-    pub normals:   Vec<Vec3>,
-    // índices triangulados: (v_idx, vt_idx?, vn_idx?)
-    pub indices:   Vec<(u32, Option<u32>, Option<u32>)>,
+const WIDTH: usize = 800;  // tamaño inicial de la ventana y del frame headless
+const HEIGHT: usize = 600;
+const MOUSE_ORBIT_SENS: f32 = 0.01; // rad por píxel arrastrado
+const SCROLL_ZOOM_SPEED: f32 = 0.1; // unidades de cam_dist por paso de rueda
+const MOUSE_PAN_SENS: f32 = 0.0012; // unidades de cámara por píxel, por unidad de cam_dist
+const PAN_KEY_SPEED: f32 = 0.01;    // por frame con Shift+flechas, por unidad de cam_dist
+const TURNTABLE_SPEED: f32 = 0.5;   // rad/s en modo turntable
+const FOV_STEP: f32 = 1.0;          // grados por frame con Z/X pulsada
+const MORPH_SPEED: f32 = 0.5;       // unidades de t por segundo con J pulsada
+
+const CONTROLS: &str = "\
+Controles:
+  A/D      rotar Y
+  ↑/↓      rotar X
+  W/S      zoom (distancia de cámara)
+  Z/X      campo de visión -/+
+  R        restablecer cámara
+  T        turntable (giro continuo en Y)
+  Shift+←↑→↓ desplazar (pan)
+  ratón    arrastrar izq. para orbitar, central para pan, rueda para zoom
+  O        perspectiva / ortográfica
+  F        captura PNG (Shift+F: PPM)
+  E        exportar STL
+  H        HUD (FPS, vértices, triángulos)
+  I        ejes XYZ en la esquina
+  G        rejilla en el suelo (plano XZ)
+  C        backface culling sí/no
+  M        antialiasing 2x2 (supermuestreo, más lento)
+  B        ver el z-buffer (cerca blanco, lejos negro)
+  U        iluminación sí/no
+  Q        brillo especular sí/no
+  N        textura: vecino más cercano / bilineal
+  K        un color por grupo o/g
+  L        wireframe sobre el relleno (Shift+L: nube de puntos)
+  P        interpolación afín / con corrección de perspectiva (en ortográfica siempre afín)
+  V        ver las normales
+  Y        matcap (color según la orientación, sin luz)
+  1-9      recuperar la vista guardada (Ctrl+1-9: guardarla; se guardan en <obj>.views.json)
+  Shift+1-9 / Shift+0  ver solo ese grupo o/g / todos
+  J        morph hacia la segunda malla (Shift+J: vuelta a la primera)
+  espacio  pausa de la animación (--anim); ←/→ en pausa: frame a frame
+  ESC      salir";
+
+// <prefijo>_<segundos>_<ms>.<ext> en el directorio actual
+fn timestamped_path(prefix: &str, ext: &str) -> PathBuf {
+    let t = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    PathBuf::from(format!("{prefix}_{}_{:03}.{ext}", t.as_secs(), t.subsec_millis()))
 }
 
-impl Mesh {
-    pub fn new() -> Self {
-        Self { positions: vec![], texcoords: vec![], normals: vec![], indices: vec![] }
+/* ==== Inspección (--info) ==== */
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
     }
+    out.push('"');
+    out
 }
 
-/* ==== Lector OBJ mínimo (v, vt, vn, f) ==== */
-fn resolve_idx(obj_idx: i32, len: usize) -> Option<u32> {
-    if len == 0 { return None; }
-    let idx0 = if obj_idx > 0 {(obj_idx - 1) as isize} else {(len as isize) + (obj_idx as isize)};
-    if idx0 < 0 || (idx0 as usize) >= len { return None; }
-    Some(idx0 as u32)
+// Estadísticas de todas las mallas juntas tal como se leyeron (antes de generar normales)
+fn scene_info_json(paths: &[String], meshes: &[Mesh]) -> String {
+    let sum = |f: fn(&Mesh) -> usize| meshes.iter().map(f).sum::<usize>();
+    let list = |items: Vec<&str>| items.into_iter().map(json_str).collect::<Vec<_>>().join(", ");
+    let vec3 = |Vec3(x, y, z): Vec3| format!("[{x}, {y}, {z}]");
+    let (min, max) = scene_bounds(meshes);
+    format!(
+        r#"{{
+  "files": [{}],
+  "vertices": {},
+  "texcoords": {},
+  "normals": {},
+  "triangles": {},
+  "lines": {},
+  "groups": [{}],
+  "materials": [{}],
+  "comments": [{}],
+  "bbox": {{ "min": {}, "max": {} }}
+}}"#,
+        list(paths.iter().map(String::as_str).collect()),
+        sum(|m| m.positions.len()),
+        sum(|m| m.texcoords.len()),
+        sum(|m| m.normals.len()),
+        sum(|m| m.indices.len() / 3),
+        sum(|m| m.lines.len()),
+        // las caras sin `o`/`g` forman un grupo "" que no se lista
+        list(meshes.iter().flat_map(|m| m.groups.iter().map(|(name, _)| name.as_str())).filter(|n| !n.is_empty()).collect()),
+        list(meshes.iter().flat_map(|m| m.materials.iter().map(|mat| mat.name.as_str())).collect()),
+        list(meshes.iter().flat_map(|m| m.comments.iter().map(String::as_str)).collect()),
+        vec3(min), vec3(max),
+    )
+}
+
+/* ==== Vistas guardadas (Ctrl+1..9 guarda, 1..9 recupera) ==== */
+type Views = [Option<Camera>; 9];
+
+// Junto al primer archivo: modelo.obj -> modelo.obj.views.json
+fn views_path(obj_path: &str) -> PathBuf {
+    PathBuf::from(format!("{obj_path}.views.json"))
 }
 
-fn parse_face_vertex(token: &str, vlen: usize, vtlen: usize, vnlen: usize)
-    -> Option<(u32, Option<u32>, Option<u32>)>
-{
-    let parts: Vec<&str> = token.split('/').collect();
-    let v  = resolve_idx(parts.get(0)?.parse::<i32>().ok()?, vlen)?;
-    let vt = if parts.len() >= 2 && !parts[1].is_empty() {
-        resolve_idx(parts[1].parse::<i32>().ok()?, vtlen)
-    } else { None };
-    let vn = if parts.len() >= 3 && !parts[2].is_empty() {
-        resolve_idx(parts[2].parse::<i32>().ok()?, vnlen)
-    } else { None };
-    Some((v, vt, vn))
+// Un objeto por línea con los campos de Camera que definen la pose; near/far y la
+// distancia de encuadre no se guardan (salen de la CLI y del modelo de esta sesión)
+fn views_json(views: &Views) -> String {
+    let slots: Vec<String> = views.iter().enumerate().filter_map(|(i, v)| v.map(|c| format!(
+        r#"  "{}": {{ "angle_y": {}, "angle_x": {}, "fov_deg": {}, "cam_dist": {}, "pan": [{}, {}], "ortho": {} }}"#,
+        i + 1, c.angle_y, c.angle_x, c.fov_deg, c.cam_dist, c.pan.0, c.pan.1, c.ortho,
+    ))).collect();
+    format!("{{\n{}\n}}\n", slots.join(",\n"))
 }
 
-fn triangulate_fan<T: Copy>(poly: &[T]) -> Vec<[T;3]> {
-    let mut tris = Vec::new();
-    for i in 2..poly.len() { tris.push([poly[0], poly[i-1], poly[i]]); }
-    tris
+// Lee lo que escribe views_json (no es un parser JSON general); los campos que falten
+// se toman de `base`. Las líneas que no se entienden se ignoran.
+fn parse_views(text: &str, base: &Camera) -> Views {
+    let mut views: Views = [None; 9];
+    // texto tras `"key":` hasta la coma o llave siguiente (los corchetes de `pan` incluidos)
+    let field = |line: &str, key: &str| -> Option<String> {
+        let rest = &line[line.find(&format!("\"{key}\":"))? + key.len() + 3..];
+        let end = if rest.trim_start().starts_with('[') { rest.find(']')? + 1 } else { rest.find([',', '}'])? };
+        Some(rest[..end].trim().to_string())
+    };
+    let num = |line: &str, key: &str, default: f32| field(line, key).and_then(|v| v.parse().ok()).unwrap_or(default);
+    for line in text.lines().map(str::trim) {
+        let Some(slot) = line.strip_prefix('"').and_then(|l| l.chars().next()).and_then(|c| c.to_digit(10)) else { continue };
+        if !(1..=9).contains(&slot) { continue; }
+        let pan = field(line, "pan").and_then(|v| {
+            let (x, y) = v.trim_matches(['[', ']']).split_once(',')?;
+            Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+        });
+        views[slot as usize - 1] = Some(Camera {
+            angle_y: num(line, "angle_y", base.angle_y),
+            angle_x: num(line, "angle_x", base.angle_x),
+            fov_deg: num(line, "fov_deg", base.fov_deg),
+            cam_dist: num(line, "cam_dist", base.cam_dist),
+            pan: pan.unwrap_or(base.pan),
+            ortho: field(line, "ortho").map_or(base.ortho, |v| v == "true"),
+            ..*base
+        });
+    }
+    views
 }
 
-fn load_obj<P: AsRef<Path>>(path: P) -> Result<Mesh, String> {
-    let file = File::open(path.as_ref()).map_err(|e| format!("No se pudo abrir: {e}"))?;
-    let reader = BufReader::new(file);
+/* ==== Línea de comandos ==== */
+const USAGE: &str = "\
+Uso: lector_obj [archivo.obj | carpeta | 'patrón*.obj' ...] [opciones]
+  --headless        renderiza un frame a archivo sin abrir ventana
+  --out <ruta>      imagen de salida en modo headless (por defecto frame.png; .ppm -> PPM)
+  --yaw <rad>       rotación Y inicial (0.6)
+  --pitch <rad>     rotación X inicial (0.0)
+  --fov <grados>    campo de visión (60)
+  --dist <u>        distancia de cámara (por defecto, la que encuadra el modelo)
+  --margin <f>      margen al encuadrar, fracción de la vista (0.1)
+  --near <z>        plano de recorte cercano en z de cámara (0.01)
+  --far <z>         plano de recorte lejano en z de cámara (1000)
+  --texture <img>   textura difusa para las caras con vt
+  --bilinear        filtrado bilineal de la textura
+  --no-cull         pinta también las caras traseras
+  --no-light        sin iluminación (color plano)
+  --points          nube de puntos (bloques por vértice, más grandes cerca de la cámara)
+  --specular        brillo especular de Phong
+  --shininess <n>   exponente del brillo (32)
+  --msaa            antialiasing por supermuestreo 2x2
+  --stats           tiempos por etapa en stderr (cada 60 frames)
+  --bg <RRGGBB>     color de fondo (101014)
+  --gradient-bg     fondo en degradado vertical, de más claro arriba a --bg abajo
+  --color <RRGGBB>  color de relleno de las mallas (por defecto, uno por archivo)
+  --skip-bad-faces  salta las caras con índices inválidos en vez de fallar
+  --drop-degenerate descarta los triángulos de área nula al cargar
+  --morph <t>       interpola entre las dos primeras mallas (misma topología)
+  --anim            los archivos son frames de una animación (espacio pausa, ←/→ paso a paso)
+  --anim-fps <n>    velocidad de la animación (24)
+  --grid <n>        semiextensión de la rejilla en unidades del modelo (10)
+  --fps <n>         FPS objetivo de la ventana (60; 0 sin límite)
+  --size <AxB>      tamaño del frame (800x600)
+  --compare <ppm>   renderiza sin ventana al tamaño del PPM y lo compara (sale con 1 si difiere)
+  --tolerance <n>   diferencia por canal permitida al comparar (2)
+  --info            estadísticas de la escena en JSON por stdout y sale
+  --export <ruta>   exporta la primera malla y sale (.ply -> PLY ascii, si no STL binario)";
+
+#[derive(Debug)]
+struct Args {
+    obj_paths: Vec<String>,
+    headless: bool,
+    out: PathBuf,
+    camera: Camera,
+    render: RenderOptions,
+    load: LoadOptions,
+    texture: Option<PathBuf>,
+    export: Option<PathBuf>,
+    grid_extent: i32,
+    bg: u32,
+    gradient_bg: bool, // degradado de DEFAULT_GRADIENT_TOP a `bg` en vez de fondo liso
+    color: Option<u32>,
+    msaa: bool,
+    fit_view: bool, // calcular cam_dist con fit_camera al cargar
+    fit_far: bool,  // sin --far: alejar el plano lejano si el encuadre queda más allá
+    margin: f32,
+    anim: bool,     // los archivos son frames de una animación, no piezas de una escena
+    anim_fps: f32,
+    morph: Option<f32>, // t inicial de la interpolación entre las dos primeras mallas
+    info: bool,         // --info: estadísticas en JSON y salir
+    size: (usize, usize),    // frame headless y tamaño inicial de la ventana
+    compare: Option<PathBuf>, // --compare: golden PPM contra el que se compara el render headless
+    tolerance: u8,           // diferencia máxima por canal que aún cuenta como igual
+    fps: u32,                // FPS objetivo de la ventana (0: sin límite)
+}
 
-    let mut mesh = Mesh::new();
+const DEFAULT_ANIM_FPS: f32 = 24.0;
+const DEFAULT_TOLERANCE: u8 = 2;
+const DEFAULT_FPS: u32 = 60;
+
+// `*` y `?` sobre un nombre de archivo (sin separadores)
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => wildcard_match(&pattern[1..], name) || (!name.is_empty() && wildcard_match(pattern, &name[1..])),
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
 
-    for (lineno, line_res) in reader.lines().enumerate() {
-        let line = line_res.map_err(|e| format!("Error L{}: {e}", lineno+1))?;
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') { continue; }
+// Una carpeta se expande a sus .obj/.obj.gz y un patrón con `*`/`?` a los archivos que
+// casan (en orden alfabético, así frame_0001.obj, frame_0002.obj... salen en secuencia);
+// cualquier otra cosa se toma como ruta tal cual
+fn expand_obj_arg(arg: &str) -> Result<Vec<String>, String> {
+    let path = Path::new(arg);
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let (dir, pattern) = if path.is_dir() {
+        (path, None)
+    } else if name.contains(['*', '?']) {
+        (path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")), Some(name))
+    } else {
+        return Ok(vec![arg.to_string()]);
+    };
+
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("No se pudo leer {}: {e}", dir.display()))?;
+    let mut found: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            let n = p.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            match pattern {
+                Some(pat) => wildcard_match(pat.as_bytes(), n.as_bytes()),
+                None => n.ends_with(".obj") || n.ends_with(".obj.gz"),
+            }
+        })
+        .map(|p| p.to_string_lossy().into_owned())
+        .collect();
+    if found.is_empty() { return Err(format!("Ningún OBJ coincide con '{arg}'")); }
+    found.sort();
+    Ok(found)
+}
 
-        let mut it = line.split_whitespace();
-        let tag = it.next().unwrap_or_default();
+fn parse_args<I: Iterator<Item = String>>(mut it: I) -> Result<Args, String> {
+    let mut args = Args {
+        obj_paths: Vec::new(),
+        headless: false,
+        out: PathBuf::from("frame.png"),
+        camera: Camera::default(),
+        render: RenderOptions::default(),
+        texture: None,
+        export: None,
+        grid_extent: 10,
+        bg: DEFAULT_BG,
+        gradient_bg: false,
+        color: None,
+        msaa: false,
+        fit_view: true,
+        fit_far: true,
+        margin: DEFAULT_FIT_MARGIN,
+        anim: false,
+        anim_fps: DEFAULT_ANIM_FPS,
+        load: LoadOptions::default(),
+        morph: None,
+        info: false,
+        size: (WIDTH, HEIGHT),
+        compare: None,
+        tolerance: DEFAULT_TOLERANCE,
+        fps: DEFAULT_FPS,
+    };
+
+    fn value<I: Iterator<Item = String>>(it: &mut I, flag: &str) -> Result<String, String> {
+        it.next().ok_or_else(|| format!("Falta el valor de {flag}"))
+    }
+    fn number<I: Iterator<Item = String>>(it: &mut I, flag: &str) -> Result<f32, String> {
+        let v = value(it, flag)?;
+        v.parse().map_err(|_| format!("Valor inválido para {flag}: '{v}'"))
+    }
+    // RRGGBB (se admite '#' delante) -> 0x00RRGGBB
+    fn hex_color<I: Iterator<Item = String>>(it: &mut I, flag: &str) -> Result<u32, String> {
+        let v = value(it, flag)?;
+        let hex = v.strip_prefix('#').unwrap_or(&v);
+        if hex.len() != 6 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Color inválido para {flag}: '{v}' (se espera RRGGBB)"));
+        }
+        Ok(u32::from_str_radix(hex, 16).unwrap())
+    }
 
-        match tag {
-            "v" => {
-                let xs: Vec<&str> = it.collect();
-                if xs.len() < 3 { return Err(format!("v inválido L{}", lineno+1)); }
-                mesh.positions.push(Vec3(xs[0].parse().unwrap(), xs[1].parse().unwrap(), xs[2].parse().unwrap()));
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--headless" => args.headless = true,
+            "--out"   => args.out = PathBuf::from(value(&mut it, &a)?),
+            "--yaw"   => args.camera.angle_y = number(&mut it, &a)?,
+            "--pitch" => args.camera.angle_x = number(&mut it, &a)?,
+            "--fov"   => args.camera.fov_deg = number(&mut it, &a)?,
+            "--near"  => args.camera.near = number(&mut it, &a)?,
+            "--far"   => {
+                args.camera.far = number(&mut it, &a)?;
+                args.fit_far = false;
             }
-            "vt" => {
-                let xs: Vec<&str> = it.collect();
-                if xs.len() < 2 { return Err(format!("vt inválido L{}", lineno+1)); }
-                mesh.texcoords.push(Vec2(xs[0].parse().unwrap(), xs[1].parse().unwrap()));
+            "--dist"  => {
+                args.camera.cam_dist = number(&mut it, &a)?;
+                args.fit_view = false;
             }
-            "vn" => {
-                let xs: Vec<&str> = it.collect();
-                if xs.len() < 3 { return Err(format!("vn inválido L{}", lineno+1)); }
-                mesh.normals.push(Vec3(xs[0].parse().unwrap(), xs[1].parse().unwrap(), xs[2].parse().unwrap()));
+            "--margin" => args.margin = number(&mut it, &a)?.clamp(0.0, 0.9),
+            "--no-cull" => args.render.cull_backfaces = false,
+            "--no-light" => args.render.lighting = false,
+            "--points"  => args.render.points = true,
+            "--specular" => args.render.specular = true,
+            "--bilinear" => args.render.bilinear = true,
+            "--shininess" => args.render.shininess = number(&mut it, &a)?.max(1.0),
+            "--msaa"    => args.msaa = true,
+            "--stats"   => args.render.stats = true,
+            "--bg"      => args.bg = hex_color(&mut it, &a)?,
+            "--gradient-bg" => args.gradient_bg = true,
+            "--color"   => args.color = Some(hex_color(&mut it, &a)?),
+            "--texture" => args.texture = Some(PathBuf::from(value(&mut it, &a)?)),
+            "--grid"    => args.grid_extent = number(&mut it, &a)?.max(0.0) as i32,
+            "--export"  => args.export = Some(PathBuf::from(value(&mut it, &a)?)),
+            "--skip-bad-faces" => args.load.skip_bad_faces = true,
+            "--drop-degenerate" => args.load.drop_degenerate = true,
+            "--info"    => args.info = true,
+            "--size"    => {
+                let v = value(&mut it, &a)?;
+                let dims = v.split_once('x').and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+                args.size = dims.filter(|&(w, h)| w > 0 && h > 0)
+                    .ok_or_else(|| format!("Tamaño inválido para --size: '{v}' (se espera ANCHOxALTO)"))?;
             }
-            "f" => {
-                let face_tokens: Vec<String> = it.map(|s| s.to_string()).collect();
-                if face_tokens.len() < 3 { return Err(format!("f < 3 vértices L{}", lineno+1)); }
-                let mut poly: Vec<(u32, Option<u32>, Option<u32>)> = Vec::new();
-                for t in &face_tokens {
-                    poly.push(parse_face_vertex(t, mesh.positions.len(), mesh.texcoords.len(), mesh.normals.len())
-                        .ok_or_else(|| format!("Índice inválido L{}", lineno+1))?);
-                }
-                for tri in triangulate_fan(&poly) {
-                    mesh.indices.push(tri[0]);
-                    mesh.indices.push(tri[1]);
-                    mesh.indices.push(tri[2]);
-                }
+            "--compare" => {
+                args.compare = Some(PathBuf::from(value(&mut it, &a)?));
+                args.headless = true;
             }
-            _ => {}
+            "--fps"     => args.fps = number(&mut it, &a)?.max(0.0) as u32,
+            "--tolerance" => args.tolerance = number(&mut it, &a)?.clamp(0.0, 255.0) as u8,
+            "--morph"   => args.morph = Some(number(&mut it, &a)?.clamp(0.0, 1.0)),
+            "--anim"    => args.anim = true,
+            "--anim-fps" => args.anim_fps = number(&mut it, &a)?.max(0.1),
+            s if s.starts_with("--") => return Err(format!("Opción desconocida: {s}")),
+            _ => args.obj_paths.extend(expand_obj_arg(&a)?),
         }
     }
-
-    Ok(mesh)
-}
-
-/* ==== Utilidades matemáticas y proyección ==== */
-fn center_and_scale_to_unit(positions: &[Vec3]) -> (Vec<Vec3>, f32, f32, f32) {
-    // bbox
-    let (mut minx, mut miny, mut minz) = (f32::INFINITY, f32::INFINITY, f32::INFINITY);
-    let (mut maxx, mut maxy, mut maxz) = (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
-    for &Vec3(x,y,z) in positions {
-        if x<minx {minx=x} if y<miny {miny=y} if z<minz {minz=z}
-        if x>maxx {maxx=x} if y>maxy {maxy=y} if z>maxz {maxz=z}
+    let (near, far) = (args.camera.near, args.camera.far);
+    if !(near > 0.0 && near < far) {
+        return Err(format!("Planos de recorte inválidos: se necesita 0 < near < far (near {near}, far {far})"));
     }
-    let cx = 0.5*(minx+maxx); let cy = 0.5*(miny+maxy); let cz = 0.5*(minz+maxz);
-    let sx = maxx-minx; let sy = maxy-miny; let sz = maxz-minz;
-    let max_extent = sx.max(sy).max(sz).max(1e-6);
-    // quepa razonable en perspectiva
-    let s = 2.0 / max_extent;
-
-    let out = positions.iter().map(|&Vec3(x,y,z)| Vec3((x-cx)*s, (y-cy)*s, (z-cz)*s)).collect();
-    (out, cx, cy, s)
-}
-
-fn project_perspective_to_screen(
-    pts: &[Vec3],
-    angle_y: f32,   // yaw
-    angle_x: f32,   // pitch
-    fov_deg: f32,
-    cam_dist: f32,
-) -> (Vec<(f32,f32)>, Vec<f32>) {
-    let (cw, ch) = (WIDTH as f32, HEIGHT as f32);
-    let half_min = 0.5 * cw.min(ch);
-    let f = 1.0 / (0.5 * fov_deg.to_radians()).tan();
-
-    let (cy, sy) = (angle_y.cos(), angle_y.sin());
-    let (cx, sx) = (angle_x.cos(), angle_x.sin());
-
-    let mut out = Vec::with_capacity(pts.len());
-    let mut depths = Vec::with_capacity(pts.len());
-
-    for &Vec3(x,y,z) in pts {
-        // Rotación en Y (yaw)
-        let xr = x*cy + z*sy;
-        let yr = y;
-        let zr = -x*sy + z*cy;
-
-        // Rotación en X (pitch) sobre el resultado anterior
-        let xrx = xr;
-        let yrx = yr*cx - zr*sx;
-        let zrx = yr*sx + zr*cx;
-
-        // Traslación hacia cámara (cámara en origen mirando +Z)
-        let zc = zrx + cam_dist; // > 0
-        depths.push(zc);
-
-        // Proyección perspectiva
-        let px = (xrx * f) / zc;
-        let py = (yrx * f) / zc;
-
-        // A coordenadas de pantalla
-        let sx = px * half_min + cw*0.5;
-        let sy = -py * half_min + ch*0.5;
-        out.push((sx, sy));
+    if args.obj_paths.is_empty() {
+        args.obj_paths.push("tie.obj".to_string());
     }
-    (out, depths)
+    Ok(args)
 }
 
-/* ==== Framebuffer con z-buffer y ventana ==== */
-struct Frame {
-    w: usize,
-    h: usize,
-    color: Vec<u32>, // 0x00RRGGBB
-    depth: Vec<f32>, // z-buffer (menor = más cerca)
+const STATS_EVERY: u32 = 60; // frames por línea de --stats
+
+// Media por frame en ms de cada etapa
+fn format_stats(t: &StageTimes, update: Option<Duration>, frames: u32) -> String {
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0 / frames as f64;
+    let mut line = format!(
+        "stats: proyección {:.2} ms | triángulos {:.2} ms | raster {:.2} ms",
+        ms(t.project), ms(t.triangles), ms(t.raster),
+    );
+    if let Some(u) = update { line += &format!(" | ventana {:.2} ms", ms(u)); }
+    line + &format!(" ({frames} frame{})", if frames == 1 { "" } else { "s" })
 }
 
-impl Frame {
-    fn new(w: usize, h: usize) -> Self {
-        Self { w, h, color: vec![0x101014; w*h], depth: vec![f32::INFINITY; w*h] }
-    }
-    fn clear(&mut self, rgb: u32) {
-        self.color.fill(rgb);
-        self.depth.fill(f32::INFINITY);
-    }
-    #[inline]
-    fn put_pixel_z(&mut self, x: i32, y: i32, z: f32, rgb: u32) {
-        if x<0 || y<0 {return;}
-        let (x, y) = (x as usize, y as usize);
-        if x>=self.w || y>=self.h {return;}
-        let idx = y*self.w + x;
-        if z < self.depth[idx] {
-            self.depth[idx] = z;
-            self.color[idx] = rgb;
+/* ==== App: ventana + loop ==== */
+fn main() {
+    let mut args = match parse_args(std::env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut meshes = Vec::with_capacity(args.obj_paths.len());
+    for obj_path in &args.obj_paths {
+        if !Path::new(obj_path).exists() {
+            eprintln!("No se encontró '{}'. Colócalo en la raíz del proyecto.", obj_path);
+            std::process::exit(1);
+        }
+        let mesh = load_obj(obj_path, &args.load).expect("Error leyendo OBJ");
+        if mesh.skipped_faces > 0 {
+            eprintln!("Aviso: {obj_path}: se omitieron {} caras con índices inválidos", mesh.skipped_faces);
         }
+        if mesh.dropped_degenerate > 0 {
+            eprintln!("{obj_path}: se descartaron {} triángulos degenerados", mesh.dropped_degenerate);
+        }
+        meshes.push(mesh);
     }
-}
 
-/* ==== Raster de triángulo con z (bary) ==== */
-#[inline] fn edge(ax:f32, ay:f32, bx:f32, by:f32, px:f32, py:f32) -> f32 {
-    (px-ax)*(by-ay) - (py-ay)*(bx-ax)
-}
+    if args.info {
+        println!("{}", scene_info_json(&args.obj_paths, &meshes));
+        return;
+    }
 
-// v: (x,y,z_cam) – z_cam para z-buffer
-fn fill_triangle_z(
-    fb: &mut Frame,
-    v0: (f32,f32,f32),
-    v1: (f32,f32,f32),
-    v2: (f32,f32,f32),
-    rgb: u32,
-) {
-    let (x0,y0,z0) = v0; let (x1,y1,z1) = v1; let (x2,y2,z2) = v2;
-
-    let min_x = x0.min(x1).min(x2).floor().max(0.0) as i32;
-    let max_x = x0.max(x1).max(x2).ceil().min((fb.w-1) as f32) as i32;
-    let min_y = y0.min(y1).min(y2).floor().max(0.0) as i32;
-    let max_y = y0.max(y1).max(y2).ceil().min((fb.h-1) as f32) as i32;
-
-    let area = edge(x0,y0, x1,y1, x2,y2);
-    if area == 0.0 { return; }
-
-    for y in min_y..=max_y {
-        for x in min_x..=max_x {
-            let px = x as f32 + 0.5;
-            let py = y as f32 + 0.5;
-
-            let w0 = edge(x1,y1, x2,y2, px,py);
-            let w1 = edge(x2,y2, x0,y0, px,py);
-            let w2 = edge(x0,y0, x1,y1, px,py);
-
-            if (w0>=0.0 && w1>=0.0 && w2>=0.0 && area>0.0) ||
-               (w0<=0.0 && w1<=0.0 && w2<=0.0 && area<0.0) {
-
-                // bary normalizadas
-                let b0 = w0/area;
-                let b1 = w1/area;
-                let b2 = w2/area;
-
-                // z_cam interpolada (correcto para z-buffer)
-                let z = b0*z0 + b1*z1 + b2*z2;
-
-                fb.put_pixel_z(x, y, z, rgb);
+    if let Some(out) = &args.export {
+        if meshes.len() > 1 {
+            eprintln!("Aviso: --export solo escribe la primera malla ({})", args.obj_paths[0]);
+        }
+        match export_mesh(&meshes[0], out) {
+            Ok(()) => println!("Malla exportada: {}", out.display()),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
             }
         }
+        return;
     }
-}
 
-/* ==== App: ventana + loop ==== */
-fn main() {
-    let obj_path = "tie.obj";
-    if !Path::new(obj_path).exists() {
-        eprintln!("No se encontró '{}'. Colócalo en la raíz del proyecto.", obj_path);
-        std::process::exit(1);
+    // Sin `vn` en el archivo, normales generadas respetando los grupos `s` (solo para el render;
+    // la exportación de arriba conserva la malla tal cual se leyó)
+    for mesh in meshes.iter_mut().filter(|m| m.normals.is_empty()) {
+        mesh.compute_vertex_normals();
+    }
+    // solo `v` (escaneos): sin caras ni líneas que pintar, se ven como nube de puntos
+    if meshes.iter().all(|m| m.indices.is_empty() && m.lines.is_empty()) { args.render.points = true; }
+
+    // --morph: las dos primeras mallas son los extremos; se pinta una copia interpolada
+    let mut morphed = None;
+    let mut morph_t = args.morph.unwrap_or(0.0);
+    if args.morph.is_some() {
+        match meshes.get(1) {
+            Some(b) if meshes[0].same_topology(b) => {
+                let mut m = meshes[0].clone();
+                m.blend_from(&meshes[0], b, morph_t);
+                morphed = Some(m);
+            }
+            _ => eprintln!("Aviso: --morph necesita dos mallas con la misma topología; se muestra solo la primera"),
+        }
     }
-    let mesh = load_obj(obj_path).expect("Error leyendo OBJ");
 
-    // Normaliza a unidad
-    let (model_unit, _, _, _) = center_and_scale_to_unit(&mesh.positions);
+    // --compare: el golden fija el tamaño del render
+    let golden = match args.compare.as_deref().map(read_ppm).transpose() {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    if let Some((w, h, _)) = &golden { args.size = (*w, *h); }
+
+    // Normaliza a unidad con el bbox conjunto de todas las mallas
+    let model = center_and_scale_to_unit(&meshes);
+    // Sin --dist, la cámara se aleja lo justo para encuadrar la escena; R vuelve a esta distancia
+    if args.fit_view {
+        args.camera.cam_dist = fit_scene(&meshes, &model, args.camera.fov_deg, aspect(args.size), args.margin);
+        // con un fov muy cerrado la distancia de encuadre pasa del far por defecto (1000); el radio de la
+        // escena nunca supera cam_dist, así que 2 * cam_dist la deja entera dentro
+        if args.fit_far { args.camera.far = args.camera.far.max(2.0 * args.camera.cam_dist); }
+    }
+    args.camera.home_dist = args.camera.cam_dist;
+    let counts = |ms: &[Mesh]| -> (usize, usize) {
+        (ms.iter().map(|m| m.positions.len()).sum(), ms.iter().map(|m| m.indices.len() / 3).sum())
+    };
+
+    let texture = match args.texture.as_deref().map(Texture::load).transpose() {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let mut frame = Frame::new(args.size.0, args.size.1);
+    // frame al doble de resolución para el supermuestreo (se crea al activarlo)
+    let mut hires = Frame::new(0, 0);
+    let style = SceneStyle {
+        bg: args.bg,
+        gradient_bg: args.gradient_bg,
+        color: args.color,
+        shared_color: args.anim, // en una animación todos los frames comparten el color del primero
+        grid: false,
+        grid_extent: args.grid_extent,
+        msaa: args.msaa,
+    };
+    // mallas a pintar: todas, o el frame actual en modo --anim; con morph se pinta la copia
+    // interpolada en lugar de esto
+    let shown_of = |anim_frame: usize| -> &[Mesh] {
+        if args.anim {
+            &meshes[anim_frame..anim_frame + 1]
+        } else if args.morph.is_some() {
+            &meshes[..1] // topologías distintas: solo la primera
+        } else {
+            &meshes
+        }
+    };
+
+    // Sin ventana: un solo frame a archivo (en --anim, el primero)
+    if args.headless {
+        let shown = morphed.as_ref().map_or(shown_of(0), std::slice::from_ref);
+        let scene = Scene { meshes: shown, model, texture: texture.as_ref() };
+        let times = render_into(&mut frame, &mut hires, &scene, &args.camera, &args.render, &style);
+        if args.render.stats { eprintln!("{}", format_stats(&times, None, 1)); }
+        if let Some((_, _, expected)) = &golden {
+            let diff = count_diff_pixels(&frame.color, expected, args.tolerance);
+            if diff > 0 {
+                eprintln!("{diff} píxeles distintos del golden (tolerancia {})", args.tolerance);
+                std::process::exit(1);
+            }
+            println!("Igual que el golden");
+            return;
+        }
+        let is_ppm = args.out.extension().is_some_and(|e| e.eq_ignore_ascii_case("ppm"));
+        let saved = if is_ppm { write_ppm(&frame, &args.out) } else { save_png(&frame.color, frame.w, frame.h, &args.out) };
+        match saved {
+            Ok(()) => println!("Frame guardado: {}", args.out.display()),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
     // Ventana
     let mut window = Window::new("OBJ Viewer (A/D rotar Y, ↑/↓ rotar X, W/S zoom, ESC salir)",
-                                 WIDTH, HEIGHT,
-                                 WindowOptions::default())
+                                 args.size.0, args.size.1,
+                                 WindowOptions { resize: true, ..WindowOptions::default() })
                      .expect("No se pudo crear ventana");
-    window.limit_update_rate(Some(std::time::Duration::from_micros(16_666))); // ~60 FPS
+    // el ritmo lo lleva el bucle (--fps), no el limitador de minifb
+    window.set_target_fps(0);
+    let frame_budget = (args.fps > 0).then(|| Duration::from_secs_f64(1.0 / args.fps as f64));
+
+    let mut camera = args.camera;
+    // vistas de la sesión anterior; al salir se escriben solo si han cambiado
+    let views_file = views_path(&args.obj_paths[0]);
+    let loaded_views = std::fs::read_to_string(&views_file).map_or([None; 9], |text| parse_views(&text, &camera));
+    let mut views = loaded_views;
+    let mut last_mouse: Option<(f32,f32)> = None;
+    let mut show_hud = true;
+    let mut show_gizmo = true;
+    let mut show_grid = false;
+    let mut turntable = false;
+    let mut msaa = args.msaa;
+    let mut show_depth = false;
+    let (mut anim_frame, mut anim_paused, mut anim_acc) = (0usize, false, 0.0f32);
+    // --stats: acumulados hasta imprimir la media
+    let (mut stats_acc, mut stats_update, mut stats_frames) = (StageTimes::default(), Duration::ZERO, 0u32);
+    let mut render_opts = args.render;
+    // Escena del último frame sin overlays y lo que la determina: si nada cambia se copia en vez
+    // de rasterizar otra vez (vista quieta, sin turntable ni animación en marcha)
+    let mut scene_cache: Vec<u32> = Vec::new();
+    let mut cached_view = None;
+    let mut last_frame = Instant::now();
+    let mut fps = 0.0f32;
+
+    println!("{CONTROLS}");
 
-    // Parámetros de cámara
-    let mut angle_y: f32 = 0.6;
-    let mut fov_deg: f32 = 60.0;
-    let mut cam_dist: f32 = 3.0;
-    let mut angle_x: f32 = 0.0;
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        // Si la ventana cambió de tamaño se rehace el frame (minimizada: 0x0, se conserva)
+        let (win_w, win_h) = window.get_size();
+        if win_w > 0 && win_h > 0 { frame.resize(win_w, win_h); }
 
-    let mut frame = Frame::new(WIDTH, HEIGHT);
-    let yellow: u32 = 0x808080; // 0xRRGGBB
+        // FPS suavizado a partir del tiempo entre frames
+        let now = Instant::now();
+        let dt = now.duration_since(last_frame).as_secs_f32();
+        last_frame = now;
+        if dt > 0.0 { fps = if fps == 0.0 { 1.0 / dt } else { 0.9 * fps + 0.1 / dt }; }
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
         // Input
-        if window.is_key_down(Key::A) { angle_y -= 0.02; }
-        if window.is_key_down(Key::D) { angle_y += 0.02; }
-        if window.is_key_down(Key::W) { cam_dist -= 0.05; if cam_dist < 1.5 { cam_dist = 1.5; } }
-        if window.is_key_down(Key::S) { cam_dist += 0.05; }
-        if window.is_key_down(Key::Up) { angle_x += 0.02; }
-        if window.is_key_down(Key::Down) { angle_x -= 0.02; }
+        if window.is_key_down(Key::A) { camera.orbit(-0.02, 0.0); }
+        if window.is_key_down(Key::D) { camera.orbit(0.02, 0.0); }
+        if window.is_key_down(Key::W) { camera.dolly(-0.05); }
+        if window.is_key_down(Key::S) { camera.dolly(0.05); }
+        // Shift+flechas: pan; flechas solas: rotar X
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        if shift {
+            let step = PAN_KEY_SPEED * camera.cam_dist;
+            if window.is_key_down(Key::Left)  { camera.pan.0 -= step; }
+            if window.is_key_down(Key::Right) { camera.pan.0 += step; }
+            if window.is_key_down(Key::Up)    { camera.pan.1 += step; }
+            if window.is_key_down(Key::Down)  { camera.pan.1 -= step; }
+        } else {
+            if window.is_key_down(Key::Up) { camera.orbit(0.0, 0.02); }
+            if window.is_key_down(Key::Down) { camera.orbit(0.0, -0.02); }
+        }
+
+        // Zoom óptico: cambia el fov sin mover la cámara
+        let fov_dir = window.is_key_down(Key::X) as i32 - window.is_key_down(Key::Z) as i32;
+        if fov_dir != 0 {
+            let old_fov = camera.fov_deg;
+            camera.zoom_fov(fov_dir as f32 * FOV_STEP);
+            if camera.fov_deg != old_fov { println!("FOV: {:.0}°", camera.fov_deg); }
+        }
 
-        // Proyección + depths (z_cam)
-        let (screen_pts, depths) = project_perspective_to_screen(&model_unit, angle_y, angle_x, fov_deg, cam_dist);
+        // Vuelve a la pose inicial (mismas constantes que los valores por defecto de la CLI)
+        if window.is_key_pressed(Key::R, KeyRepeat::No) { camera.reset(); }
+
+        // Animación: avanza a `anim_fps` con el tiempo real; en pausa, ←/→ van frame a frame
+        if args.anim {
+            if window.is_key_pressed(Key::Space, KeyRepeat::No) { anim_paused = !anim_paused; }
+            let n = meshes.len();
+            if anim_paused {
+                if !shift && window.is_key_pressed(Key::Right, KeyRepeat::Yes) { anim_frame = (anim_frame + 1) % n; }
+                if !shift && window.is_key_pressed(Key::Left, KeyRepeat::Yes) { anim_frame = (anim_frame + n - 1) % n; }
+            } else {
+                anim_acc += dt;
+                let step = 1.0 / args.anim_fps;
+                while anim_acc >= step {
+                    anim_acc -= step;
+                    anim_frame = (anim_frame + 1) % n;
+                }
+            }
+        }
 
-        // Render
-        frame.clear(0x101014);
+        // Arrastre izquierdo: órbita; central: pan. Delta del ratón respecto al frame anterior
+        let mouse = window.get_mouse_pos(MouseMode::Discard);
+        let orbiting = window.get_mouse_down(MouseButton::Left);
+        if orbiting || window.get_mouse_down(MouseButton::Middle) {
+            if let (Some((px, py)), Some((mx, my))) = (last_mouse, mouse) {
+                let (dx, dy) = (mx - px, my - py);
+                if orbiting {
+                    camera.orbit(dx * MOUSE_ORBIT_SENS, -dy * MOUSE_ORBIT_SENS);
+                } else {
+                    // el modelo sigue al cursor (y de pantalla hacia abajo)
+                    camera.pan.0 += dx * MOUSE_PAN_SENS * camera.cam_dist;
+                    camera.pan.1 -= dy * MOUSE_PAN_SENS * camera.cam_dist;
+                }
+            }
+            last_mouse = mouse;
+        } else {
+            last_mouse = None;
+        }
 
-        for tri in mesh.indices.chunks_exact(3) {
-            let i0 = tri[0].0 as usize;
-            let i1 = tri[1].0 as usize;
-            let i2 = tri[2].0 as usize;
+        // Rueda: hacia arriba acerca, hacia abajo aleja
+        if let Some((_, wheel_y)) = window.get_scroll_wheel() {
+            camera.dolly(-wheel_y * SCROLL_ZOOM_SPEED);
+        }
 
-            // descartar si algún vértice está detrás/near
-            if depths[i0] <= 0.001 || depths[i1] <= 0.001 || depths[i2] <= 0.001 { continue; }
+        if window.is_key_pressed(Key::H, KeyRepeat::No) { show_hud = !show_hud; }
+        if window.is_key_pressed(Key::I, KeyRepeat::No) { show_gizmo = !show_gizmo; }
+        if window.is_key_pressed(Key::G, KeyRepeat::No) { show_grid = !show_grid; }
+        if window.is_key_pressed(Key::T, KeyRepeat::No) { turntable = !turntable; }
+        if window.is_key_pressed(Key::B, KeyRepeat::No) { show_depth = !show_depth; }
+        if window.is_key_pressed(Key::U, KeyRepeat::No) {
+            render_opts.lighting = !render_opts.lighting;
+            println!("Iluminación: {}", if render_opts.lighting { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::N, KeyRepeat::No) {
+            render_opts.bilinear = !render_opts.bilinear;
+            println!("Filtrado de textura: {}", if render_opts.bilinear { "bilineal" } else { "vecino más cercano" });
+        }
+        // 1-9: recuperar la vista de esa ranura, Ctrl+1-9: guardarla
+        // Shift+1-9: aislar ese grupo (en cada malla el suyo), Shift+0: volver a verlo todo
+        const DIGIT_KEYS: [Key; 10] = [Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+                                       Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9];
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        let digit = DIGIT_KEYS.iter().position(|&key| window.is_key_pressed(key, KeyRepeat::No));
+        if let Some(k) = digit.filter(|&k| k > 0 && ctrl) {
+            views[k - 1] = Some(camera);
+            println!("Vista {k} guardada");
+        } else if let Some(k) = digit.filter(|&k| k > 0 && !shift) {
+            match views[k - 1] {
+                Some(v) => { camera = v; println!("Vista {k}"); }
+                None => println!("Vista {k}: vacía (Ctrl+{k} para guardarla)"),
+            }
+        } else if let Some(k) = digit.filter(|_| shift) {
+            render_opts.solo = (k > 0).then(|| k as u32 - 1);
+            match render_opts.solo {
+                None => println!("Solo: todos los grupos"),
+                Some(g) => {
+                    let names: Vec<&str> = meshes.iter()
+                        .filter_map(|m| m.groups.get(g as usize))
+                        .map(|(name, _)| if name.is_empty() { "(sin nombre)" } else { name.as_str() })
+                        .collect();
+                    if names.is_empty() {
+                        println!("Solo: grupo {k} (no existe, no se ve nada)");
+                    } else {
+                        println!("Solo: grupo {k}: {}", names.join(", "));
+                    }
+                }
+            }
+        }
+        if window.is_key_pressed(Key::Y, KeyRepeat::No) {
+            render_opts.matcap = !render_opts.matcap;
+            println!("Matcap: {}", if render_opts.matcap { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::V, KeyRepeat::No) {
+            render_opts.show_normals = !render_opts.show_normals;
+            println!("Normales: {}", if render_opts.show_normals { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            render_opts.affine = !render_opts.affine;
+            println!("Interpolación: {}", if render_opts.affine { "afín" } else { "con corrección de perspectiva" });
+        }
+        // Shift+L: nube de puntos (L sola, wireframe)
+        if shift && window.is_key_pressed(Key::L, KeyRepeat::No) {
+            render_opts.points = !render_opts.points;
+            println!("Nube de puntos: {}", if render_opts.points { "sí" } else { "no" });
+        } else if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            render_opts.wireframe = !render_opts.wireframe;
+            println!("Wireframe: {}", if render_opts.wireframe { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            render_opts.group_colors = !render_opts.group_colors;
+            println!("Color por grupo: {}", if render_opts.group_colors { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::Q, KeyRepeat::No) {
+            render_opts.specular = !render_opts.specular;
+            println!("Especular: {}", if render_opts.specular { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::M, KeyRepeat::No) {
+            msaa = !msaa;
+            println!("MSAA 2x2: {}", if msaa { "sí" } else { "no" });
+        }
+        // J / Shift+J mueven t hacia la segunda / la primera malla
+        if let Some(m) = &mut morphed && window.is_key_down(Key::J) {
+            let dir = if shift { -1.0 } else { 1.0 };
+            let t = (morph_t + dir * MORPH_SPEED * dt).clamp(0.0, 1.0);
+            if t != morph_t {
+                morph_t = t;
+                m.blend_from(&meshes[0], &meshes[1], morph_t);
+            }
+        }
+        // se suma a la órbita manual; con dt la velocidad no depende de los FPS
+        if turntable { camera.orbit(TURNTABLE_SPEED * dt, 0.0); }
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            render_opts.cull_backfaces = !render_opts.cull_backfaces;
+            println!("Backface culling: {}", if render_opts.cull_backfaces { "sí" } else { "no" });
+        }
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            camera.ortho = !camera.ortho;
+            println!("Proyección: {}", if camera.ortho { "ortográfica" } else { "perspectiva" });
+        }
 
-            let (x0,y0) = screen_pts[i0];
-            let (x1,y1) = screen_pts[i1];
-            let (x2,y2) = screen_pts[i2];
+        // Render
+        let shown = morphed.as_ref().map_or(shown_of(anim_frame), std::slice::from_ref);
+        // el tamaño del frame entra en la clave: al redimensionar se vuelve a pintar
+        let view = Some((camera, render_opts, msaa, show_grid, show_depth, anim_frame, morph_t, (frame.w, frame.h)));
+        // con --stats siempre se renderiza, para medir
+        let times = if view == cached_view && !render_opts.stats {
+            frame.color.copy_from_slice(&scene_cache);
+            StageTimes::default()
+        } else {
+            let style = SceneStyle { msaa, grid: show_grid, ..style };
+            let scene = Scene { meshes: shown, model, texture: texture.as_ref() };
+            let times = render_into(&mut frame, &mut hires, &scene, &camera, &render_opts, &style);
+            if show_depth { frame.depth_to_color(); }
+            scene_cache.clone_from(&frame.color);
+            cached_view = view;
+            times
+        };
+
+        if show_gizmo { draw_axis_gizmo(&mut frame, camera.angle_y, camera.angle_x); }
+        if show_hud {
+            let (vert_count, tri_count) = counts(shown);
+            let mut lines = vec![
+                format!("FPS {fps:.0}"),
+                format!("VERT {vert_count}"),
+                format!("TRIS {tri_count}"),
+                format!("FOV {:.0}", camera.fov_deg),
+                // en ortográfica RasterParams interpola siempre en afín (ahí es exacto) y P no cambia nada
+                format!("INTERP {}", if camera.ortho { "ORTO" } else if render_opts.affine { "AFIN" } else { "PERSP" }),
+            ];
+            if args.anim { lines.push(format!("FRAME {}/{}", anim_frame + 1, meshes.len())); }
+            if morphed.is_some() { lines.push(format!("MORPH {morph_t:.2}")); }
+            draw_hud(&mut frame, &lines);
+        }
 
-            // backface culling 2D opcional:
-            let ax = x1 - x0; let ay = y1 - y0;
-            let bx = x2 - x0; let by = y2 - y0;
-            let cross = ax*by - ay*bx;
-            if cross <= 0.0 { continue; }
+        // Captura: se copia el buffer y se codifica en otro hilo para no frenar el loop
+        // Shift+F: PPM, sin codificar, en el mismo hilo
+        if shift && window.is_key_pressed(Key::F, KeyRepeat::No) {
+            let path = timestamped_path("captura", "ppm");
+            match write_ppm(&frame, &path) {
+                Ok(()) => println!("Captura guardada: {}", path.display()),
+                Err(e) => eprintln!("{e}"),
+            }
+        } else if window.is_key_pressed(Key::F, KeyRepeat::No) {
+            let pixels = frame.color.clone();
+            let (w, h) = (frame.w, frame.h);
+            std::thread::spawn(move || {
+                let path = timestamped_path("captura", "png");
+                match save_png(&pixels, w, h, &path) {
+                    Ok(()) => println!("Captura guardada: {}", path.display()),
+                    Err(e) => eprintln!("{e}"),
+                }
+            });
+        }
 
-            fill_triangle_z(
-                &mut frame,
-                (x0, y0, depths[i0]),
-                (x1, y1, depths[i1]),
-                (x2, y2, depths[i2]),
-                yellow
-            );
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            let path = timestamped_path("export", "stl");
+            match export_stl(&meshes[0], &path) {
+                Ok(()) => println!("STL exportado: {}", path.display()),
+                Err(e) => eprintln!("{e}"),
+            }
         }
 
         // minifb espera un buffer u32 0x00RRGGBB
-        window.update_with_buffer(&frame.color, WIDTH, HEIGHT).unwrap();
+        let t_update = render_opts.stats.then(Instant::now);
+        window.update_with_buffer(&frame.color, frame.w, frame.h).unwrap();
+
+        if let Some(t) = t_update {
+            stats_acc += times;
+            stats_update += t.elapsed();
+            stats_frames += 1;
+            if stats_frames == STATS_EVERY {
+                eprintln!("{}", format_stats(&stats_acc, Some(stats_update), stats_frames));
+                (stats_acc, stats_update, stats_frames) = (StageTimes::default(), Duration::ZERO, 0);
+            }
+        }
+
+        // se duerme solo lo que sobra del presupuesto; si el frame ya se pasó, se sigue sin esperar
+        if let Some(budget) = frame_budget {
+            let spent = now.elapsed();
+            if spent < budget { std::thread::sleep(budget - spent); }
+        }
     }
-}
\ No newline at end of file
+
+    if views != loaded_views {
+        match std::fs::write(&views_file, views_json(&views)) {
+            Ok(()) => println!("Vistas guardadas: {}", views_file.display()),
+            Err(e) => eprintln!("No se pudieron guardar las vistas en {}: {e}", views_file.display()),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vistas_ida_y_vuelta() {
+        let base = Camera { near: 0.5, far: 50.0, home_dist: 3.0, ..Camera::default() };
+        let mut views: Views = [None; 9];
+        views[0] = Some(Camera { angle_y: 0.25, angle_x: -0.5, fov_deg: 35.0, cam_dist: 4.5, pan: (0.1, -0.2), ..base });
+        views[8] = Some(Camera { ortho: true, pan: (-1.5, 2.0), ..base });
+
+        let back = parse_views(&views_json(&views), &base);
+        assert_eq!(back, views);
+    }
+}
@@ -0,0 +1,416 @@
+//! Matrices, proyección a pantalla y recorte contra el plano near.
+
+use arrayvec::ArrayVec;
+
+use crate::mesh::{Mesh, Vec2, Vec3};
+
+/* ==== Utilidades matemáticas y proyección ==== */
+// (min, max) de los puntos; sin puntos, caja degenerada en el origen
+pub fn aabb(positions: &[Vec3]) -> (Vec3, Vec3) {
+    if positions.is_empty() { return (Vec3(0.0, 0.0, 0.0), Vec3(0.0, 0.0, 0.0)); }
+    let (mut minx, mut miny, mut minz) = (f32::INFINITY, f32::INFINITY, f32::INFINITY);
+    let (mut maxx, mut maxy, mut maxz) = (f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for &Vec3(x,y,z) in positions {
+        if x<minx {minx=x}
+        if y<miny {miny=y}
+        if z<minz {minz=z}
+        if x>maxx {maxx=x}
+        if y>maxy {maxy=y}
+        if z>maxz {maxz=z}
+    }
+    (Vec3(minx, miny, minz), Vec3(maxx, maxy, maxz))
+}
+
+// bbox conjunto a partir de las cajas de cada malla (las vacías no cuentan)
+pub fn scene_bounds(meshes: &[Mesh]) -> (Vec3, Vec3) {
+    let corners: Vec<Vec3> = meshes.iter()
+        .filter(|m| !m.positions.is_empty())
+        .flat_map(|m| [m.bounds.0, m.bounds.1])
+        .collect();
+    aabb(&corners)
+}
+
+// Matriz de modelo que centra el bbox conjunto de `meshes` y lo escala a tamaño 2:
+// p_unit = (p - centro) * escala. Todas las mallas comparten centro y escala.
+pub fn center_and_scale_to_unit(meshes: &[Mesh]) -> Mat4 {
+    let (Vec3(minx, miny, minz), Vec3(maxx, maxy, maxz)) = scene_bounds(meshes);
+    let cx = 0.5*(minx+maxx); let cy = 0.5*(miny+maxy); let cz = 0.5*(minz+maxz);
+    let sx = maxx-minx; let sy = maxy-miny; let sz = maxz-minz;
+    let max_extent = sx.max(sy).max(sz).max(1e-6);
+    // quepa razonable en perspectiva
+    let s = 2.0 / max_extent;
+
+    Mat4::scaling(s).mul(&Mat4::translation(-cx, -cy, -cz))
+}
+
+/* ==== Matrices 4x4 ==== */
+// Fila mayor (m[fila][col]); los puntos son columnas: M * p
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4(pub [[f32;4];4]);
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        Self([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // self * o: al transformar se aplica primero `o`
+    pub fn mul(&self, o: &Mat4) -> Mat4 {
+        let (a, b) = (&self.0, &o.0);
+        let mut m = [[0.0f32; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                *v = a[i][0]*b[0][j] + a[i][1]*b[1][j] + a[i][2]*b[2][j] + a[i][3]*b[3][j];
+            }
+        }
+        Mat4(m)
+    }
+
+    // pitch
+    pub fn rotation_x(a: f32) -> Self {
+        let (c, s) = (a.cos(), a.sin());
+        Self([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0,   c,  -s, 0.0],
+            [0.0,   s,   c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // yaw
+    pub fn rotation_y(a: f32) -> Self {
+        let (c, s) = (a.cos(), a.sin());
+        Self([
+            [  c, 0.0,   s, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [ -s, 0.0,   c, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // roll (todavía sin control en la UI)
+    pub fn rotation_z(a: f32) -> Self {
+        let (c, s) = (a.cos(), a.sin());
+        Self([
+            [  c,  -s, 0.0, 0.0],
+            [  s,   c, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(x: f32, y: f32, z: f32) -> Self {
+        Self([
+            [1.0, 0.0, 0.0,   x],
+            [0.0, 1.0, 0.0,   y],
+            [0.0, 0.0, 1.0,   z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(s: f32) -> Self {
+        Self([
+            [  s, 0.0, 0.0, 0.0],
+            [0.0,   s, 0.0, 0.0],
+            [0.0, 0.0,   s, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // Cámara en origen mirando +Z, fov vertical. w = z de cámara; z/w en [0,1] entre near y far.
+    pub fn perspective(fov_deg: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let f = focal_length(fov_deg);
+        let a = far / (far - near);
+        Self([
+            [f/aspect, 0.0, 0.0,       0.0],
+            [0.0,        f, 0.0,       0.0],
+            [0.0,      0.0,   a, -near * a],
+            [0.0,      0.0, 1.0,       0.0],
+        ])
+    }
+
+    // Sin división: escala x/y y deja la z de cámara tal cual (w = 1)
+    pub fn orthographic(scale: f32, aspect: f32) -> Self {
+        Self([
+            [scale/aspect,   0.0, 0.0, 0.0],
+            [0.0,          scale, 0.0, 0.0],
+            [0.0,            0.0, 1.0, 0.0],
+            [0.0,            0.0, 0.0, 1.0],
+        ])
+    }
+
+    // M * (x,y,z,1) sin dividir por w
+    #[inline]
+    pub fn transform_homogeneous(&self, Vec3(x,y,z): Vec3) -> [f32;4] {
+        let m = &self.0;
+        [
+            m[0][0]*x + m[0][1]*y + m[0][2]*z + m[0][3],
+            m[1][0]*x + m[1][1]*y + m[1][2]*z + m[1][3],
+            m[2][0]*x + m[2][1]*y + m[2][2]*z + m[2][3],
+            m[3][0]*x + m[3][1]*y + m[3][2]*z + m[3][3],
+        ]
+    }
+
+    // Solo la parte 3x3 (direcciones/normales; vale porque no hay escalas no uniformes)
+    #[inline]
+    pub fn transform_dir(&self, Vec3(x,y,z): Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3(
+            m[0][0]*x + m[0][1]*y + m[0][2]*z,
+            m[1][0]*x + m[1][1]*y + m[1][2]*z,
+            m[2][0]*x + m[2][1]*y + m[2][2]*z,
+        )
+    }
+
+    // M * (x,y,z,1) con división por w
+    #[inline]
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let [x, y, z, w] = self.transform_homogeneous(p);
+        Vec3(x/w, y/w, z/w)
+    }
+}
+
+/* ==== Proyección ==== */
+
+// Rango en el que tan(fov/2) es finito y no nulo, venga el fov de donde venga (--fov, teclas...)
+const SAFE_FOV: (f32, f32) = (0.1, 179.0);
+
+// max/min en vez de clamp: un NaN (`--fov nan` se parsea) acaba en el mínimo, no pasa tal cual
+#[inline]
+pub fn focal_length(fov_deg: f32) -> f32 {
+    1.0 / (0.5 * fov_deg.max(SAFE_FOV.0).min(SAFE_FOV.1).to_radians()).tan()
+}
+
+// Pantalla para puntos con zc <= 0 (en o detrás de la cámara): lejos del frame pero finito,
+// así no entran inf/NaN en el raster. El recorte near ya descarta esos triángulos.
+const OFFSCREEN: (f32, f32) = (-1.0e9, -1.0e9);
+
+// Mundo -> cámara: yaw (Y), luego pitch (X), luego alejar cam_dist (cámara en origen mirando +Z).
+// `pan` desplaza el modelo en x/y de cámara tras la rotación: se desliza sin girar.
+pub fn view_matrix(angle_y: f32, angle_x: f32, cam_dist: f32, (pan_x, pan_y): (f32,f32)) -> Mat4 {
+    Mat4::translation(pan_x, pan_y, cam_dist)
+        .mul(&Mat4::rotation_x(angle_x))
+        .mul(&Mat4::rotation_y(angle_y))
+}
+
+#[inline]
+pub fn aspect((w, h): (usize, usize)) -> f32 {
+    w as f32 / h as f32
+}
+
+// NDC [-1,1] -> píxeles de un frame `size` = (ancho, alto) (y hacia abajo)
+#[inline]
+pub fn ndc_to_screen(Vec3(x,y,_): Vec3, (w, h): (usize, usize)) -> (f32,f32) {
+    let (cw, ch) = (w as f32, h as f32);
+    ((x + 1.0) * 0.5 * cw, (1.0 - y) * 0.5 * ch)
+}
+
+// `view_proj`: proyección * vista de la cámara (Camera::view_proj_matrix) para `size`
+pub fn project_perspective_to_screen(
+    pts: &[Vec3],
+    model: &Mat4,
+    view_proj: &Mat4,
+    size: (usize, usize),
+) -> (Vec<(f32,f32)>, Vec<f32>) {
+    let mvp = view_proj.mul(model);
+
+    let mut out = Vec::with_capacity(pts.len());
+    let mut depths = Vec::with_capacity(pts.len());
+
+    // de 4 en 4 con SSE; lo que sobra (o todo, sin la feature `simd`) por el bucle escalar
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    // SAFETY: SSE2 forma parte de la base de x86_64, siempre está disponible
+    let pts = unsafe { project_perspective_sse(pts, &mvp, size, &mut out, &mut depths) };
+    for &p in pts {
+        let [x, y, _, w] = mvp.transform_homogeneous(p);
+        depths.push(w); // w = z de cámara (> 0 delante)
+        out.push(if w > 0.0 { ndc_to_screen(Vec3(x/w, y/w, 0.0), size) } else { OFFSCREEN });
+    }
+    (out, depths)
+}
+
+// Versión SSE del bucle de arriba (SSE2 siempre está en x86_64): cuatro puntos por
+// iteración, con las mismas operaciones en el mismo orden que transform_homogeneous y
+// ndc_to_screen, así que el resultado es idéntico bit a bit. Devuelve la cola (< 4 puntos).
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+fn project_perspective_sse<'a>(
+    pts: &'a [Vec3],
+    mvp: &Mat4,
+    (w, h): (usize, usize),
+    out: &mut Vec<(f32,f32)>,
+    depths: &mut Vec<f32>,
+) -> &'a [Vec3] {
+    use std::arch::x86_64::*;
+    let lanes = |v: __m128| {
+        let mut a = [0.0f32; 4];
+        // SAFETY: `a` tiene justo 4 f32 y storeu no exige alineación
+        unsafe { _mm_storeu_ps(a.as_mut_ptr(), v) };
+        a
+    };
+    let row = |r: usize| mvp.0[r].map(|k| _mm_set1_ps(k));
+    let (rx, ry, rw) = (row(0), row(1), row(3));
+    let dot = |r: &[__m128;4], x: __m128, y: __m128, z: __m128| {
+        _mm_add_ps(_mm_add_ps(_mm_add_ps(_mm_mul_ps(r[0], x), _mm_mul_ps(r[1], y)), _mm_mul_ps(r[2], z)), r[3])
+    };
+    let (one, half) = (_mm_set1_ps(1.0), _mm_set1_ps(0.5));
+    let (cw, ch) = (_mm_set1_ps(w as f32), _mm_set1_ps(h as f32));
+
+    let chunks = pts.chunks_exact(4);
+    let rest = chunks.remainder();
+    for c in chunks {
+        let x = _mm_setr_ps(c[0].0, c[1].0, c[2].0, c[3].0);
+        let y = _mm_setr_ps(c[0].1, c[1].1, c[2].1, c[3].1);
+        let z = _mm_setr_ps(c[0].2, c[1].2, c[2].2, c[3].2);
+        let (px, py, pw) = (dot(&rx, x, y, z), dot(&ry, x, y, z), dot(&rw, x, y, z));
+        let sx = _mm_mul_ps(_mm_mul_ps(_mm_add_ps(_mm_div_ps(px, pw), one), half), cw);
+        let sy = _mm_mul_ps(_mm_mul_ps(_mm_sub_ps(one, _mm_div_ps(py, pw)), half), ch);
+        // zc <= 0 (o NaN) -> OFFSCREEN: selección por máscara, como el `if w > 0.0` escalar
+        let front = _mm_cmpgt_ps(pw, _mm_setzero_ps());
+        let pick = |v: __m128, off: f32| _mm_or_ps(_mm_and_ps(front, v), _mm_andnot_ps(front, _mm_set1_ps(off)));
+        let (sx, sy) = (pick(sx, OFFSCREEN.0), pick(sy, OFFSCREEN.1));
+        let (sx, sy, pw) = (lanes(sx), lanes(sy), lanes(pw));
+        for k in 0..4 {
+            depths.push(pw[k]);
+            out.push((sx[k], sy[k]));
+        }
+    }
+    rest
+}
+
+// Igual que la perspectiva pero sin dividir por zc (`view_proj` con Mat4::orthographic).
+// La profundidad sigue siendo zc de cámara para el z-buffer.
+pub fn project_orthographic_to_screen(
+    pts: &[Vec3],
+    model: &Mat4,
+    view_proj: &Mat4,
+    size: (usize, usize),
+) -> (Vec<(f32,f32)>, Vec<f32>) {
+    let mvp = view_proj.mul(model);
+
+    let mut out = Vec::with_capacity(pts.len());
+    let mut depths = Vec::with_capacity(pts.len());
+
+    for &p in pts {
+        let [x, y, z, _] = mvp.transform_homogeneous(p);
+        depths.push(z); // la ortográfica conserva la z de cámara
+        out.push(ndc_to_screen(Vec3(x, y, 0.0), size));
+    }
+    (out, depths)
+}
+
+/* ==== Recorte contra el plano near (espacio cámara) ==== */
+#[inline]
+pub fn lerp3(Vec3(ax,ay,az): Vec3, Vec3(bx,by,bz): Vec3, t: f32) -> Vec3 {
+    Vec3(ax + (bx-ax)*t, ay + (by-ay)*t, az + (bz-az)*t)
+}
+
+// Vértice en espacio cámara con sus atributos; al recortar se interpola todo junto
+#[derive(Clone, Copy, Debug)]
+pub struct CamVertex {
+    pub p: Vec3,
+    pub n: Vec3, // normal en espacio cámara (sin normalizar tras el lerp)
+    pub color: Vec3,
+    pub uv: Vec2,
+}
+
+impl CamVertex {
+    #[inline]
+    pub fn lerp(self, o: CamVertex, t: f32) -> CamVertex {
+        let uv = Vec2(self.uv.0 + (o.uv.0 - self.uv.0)*t, self.uv.1 + (o.uv.1 - self.uv.1)*t);
+        CamVertex { p: lerp3(self.p, o.p, t), n: lerp3(self.n, o.n, t), color: lerp3(self.color, o.color, t), uv }
+    }
+}
+
+// Sutherland–Hodgman con un solo plano z = near: el triángulo queda igual,
+// se descarta, o se parte en 1–2 triángulos. Conserva el orden de vértices (winding).
+pub fn clip_triangle_near(verts: [CamVertex;3], near: f32) -> ArrayVec<[CamVertex;3], 2> {
+    let mut poly: ArrayVec<CamVertex, 4> = ArrayVec::new();
+    for i in 0..3 {
+        let a = verts[i];
+        let b = verts[(i+1) % 3];
+        let (a_in, b_in) = (a.p.2 >= near, b.p.2 >= near);
+        if a_in { poly.push(a); }
+        if a_in != b_in { poly.push(a.lerp(b, (near - a.p.2) / (b.p.2 - a.p.2))); }
+    }
+
+    let mut out = ArrayVec::new();
+    for i in 2..poly.len() { out.push([poly[0], poly[i-1], poly[i]]); }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cerca(a: Vec3, b: Vec3) -> bool {
+        (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5 && (a.2 - b.2).abs() < 1e-5
+    }
+
+    #[test]
+    fn identidad() {
+        let m = Mat4::translation(1.0, 2.0, 3.0).mul(&Mat4::rotation_x(0.3));
+        assert_eq!(Mat4::identity().mul(&m), m);
+        assert_eq!(m.mul(&Mat4::identity()), m);
+        assert_eq!(Mat4::identity().transform_point(Vec3(4.0, -5.0, 6.0)), Vec3(4.0, -5.0, 6.0));
+    }
+
+    #[test]
+    fn punto_conocido() {
+        // primero el yaw de 90° (x -> -z), luego la traslación
+        let m = Mat4::translation(1.0, 2.0, 3.0).mul(&Mat4::rotation_y(std::f32::consts::FRAC_PI_2));
+        assert!(cerca(m.transform_point(Vec3(1.0, 0.0, 0.0)), Vec3(1.0, 2.0, 2.0)));
+        // las direcciones no se trasladan
+        assert!(cerca(m.transform_dir(Vec3(1.0, 0.0, 0.0)), Vec3(0.0, 0.0, -1.0)));
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[test]
+    fn sse_igual_que_escalar() {
+        let size = (320, 240);
+        let mvp = Mat4::perspective(60.0, aspect(size), 0.01, 100.0)
+            .mul(&view_matrix(0.6, 0.2, 3.0, (0.1, -0.05)));
+        // 7 puntos: un bloque de 4 por SSE y 3 de cola; uno queda detrás de la cámara
+        let pts = [
+            Vec3(0.0, 0.0, 0.0), Vec3(1.0, -1.0, 0.5), Vec3(-0.7, 0.3, -0.9), Vec3(0.0, 0.0, -10.0),
+            Vec3(0.25, 0.5, 0.75), Vec3(-1.0, 1.0, 1.0), Vec3(2.0, 0.1, -0.3),
+        ];
+        let (mut out, mut depths) = (Vec::new(), Vec::new());
+        // SAFETY: SSE2 forma parte de la base de x86_64
+        let rest = unsafe { project_perspective_sse(&pts, &mvp, size, &mut out, &mut depths) };
+        assert_eq!(rest.len(), 3);
+        assert_eq!(out.len(), 4);
+        for (i, &p) in pts[..4].iter().enumerate() {
+            let [x, y, _, w] = mvp.transform_homogeneous(p);
+            let (sx, sy) = if w > 0.0 { ndc_to_screen(Vec3(x/w, y/w, 0.0), size) } else { OFFSCREEN };
+            assert!((out[i].0 - sx).abs() <= 1e-3 && (out[i].1 - sy).abs() <= 1e-3, "punto {i}: {:?} vs {:?}", out[i], (sx, sy));
+            assert!((depths[i] - w).abs() <= 1e-5, "profundidad {i}");
+        }
+        assert_eq!(out[3], OFFSCREEN);
+    }
+
+    #[test]
+    fn fov_extremo_sigue_finito() {
+        let size = (64, 48);
+        // un punto delante, uno en el plano de la cámara y otro detrás
+        let pts = [Vec3(0.5, 0.5, 2.0), Vec3(0.3, 0.0, 0.0), Vec3(-0.2, 0.1, -1.0)];
+        for fov in [0.0, 1e-6, 179.9, 180.0, 1000.0, -30.0, f32::NAN] {
+            assert!(focal_length(fov).is_finite() && focal_length(fov) > 0.0, "fov {fov}");
+            let view_proj = Mat4::perspective(fov, aspect(size), 0.01, 100.0);
+            let (out, depths) = project_perspective_to_screen(&pts, &Mat4::identity(), &view_proj, size);
+            for (i, &(x, y)) in out.iter().enumerate() {
+                assert!(x.is_finite() && y.is_finite(), "fov {fov}, punto {i}: {x},{y}");
+                assert!(depths[i].is_finite());
+            }
+            assert_eq!(out[1], OFFSCREEN);
+            assert_eq!(out[2], OFFSCREEN);
+            // el encuadre usa el mismo fov acotado: distancia finita
+            let dist = crate::render::fit_camera((Vec3(-1.0, -1.0, -1.0), Vec3(1.0, 1.0, 1.0)), fov, aspect(size), 0.1);
+            assert!(dist.is_finite() && dist > 0.0, "fov {fov}: dist {dist}");
+        }
+    }
+}
@@ -0,0 +1,632 @@
+//! Mallas OBJ: tipos básicos (Vec2, Vec3), la malla triangulada y el lector de .obj/.mtl.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::Range;
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+
+use crate::math::{aabb, lerp3};
+
+/* ==== Estructuras básicas ==== */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3(pub f32, pub f32, pub f32);
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vec2(pub f32, pub f32);
+
+impl std::ops::Sub for Vec3 {
+    type Output = Vec3;
+    #[inline] fn sub(self, o: Vec3) -> Vec3 { Vec3(self.0-o.0, self.1-o.1, self.2-o.2) }
+}
+
+impl Vec3 {
+    #[inline] pub fn cross(self, o: Vec3) -> Vec3 {
+        Vec3(self.1*o.2 - self.2*o.1, self.2*o.0 - self.0*o.2, self.0*o.1 - self.1*o.0)
+    }
+    #[inline] pub fn length(self) -> f32 { (self.0*self.0 + self.1*self.1 + self.2*self.2).sqrt() }
+    #[inline] pub fn dot(self, o: Vec3) -> f32 { self.0*o.0 + self.1*o.1 + self.2*o.2 }
+    #[inline] pub fn scale(self, k: f32) -> Vec3 { Vec3(self.0*k, self.1*k, self.2*k) }
+    // sin longitud se devuelve tal cual (evita NaN en normales degeneradas)
+    #[inline] pub fn normalize(self) -> Vec3 {
+        let l = self.length();
+        if l > 0.0 { self.scale(1.0 / l) } else { self }
+    }
+}
+
+impl std::ops::Add for Vec3 {
+    type Output = Vec3;
+    #[inline] fn add(self, o: Vec3) -> Vec3 { Vec3(self.0+o.0, self.1+o.1, self.2+o.2) }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub texcoords: Vec<Vec2>,
+    // tercera coordenada de `vt u v w` (texturas 3D), paralela a `texcoords`; vacío si ninguno la trae
+    pub texcoord_w: Vec<f32>,
+    pub normals:   Vec<Vec3>,
+    // RGB por vértice (`v x y z r g b`), paralelo a `positions`; vacío si el archivo no trae
+    pub colors:    Vec<Vec3>,
+    // índices triangulados: (v_idx, vt_idx?, vn_idx?)
+    pub indices:   Vec<(u32, Option<u32>, Option<u32>)>,
+    // grupos `o`/`g`: (nombre, rango en `indices`); "" = caras sin grupo
+    pub groups:    Vec<(String, Range<usize>)>,
+    // segmentos de las polilíneas `l` (índices en `positions`)
+    pub lines:     Vec<(u32, u32)>,
+    // materiales de `usemtl`, sin repetir y en orden de aparición; los valores salen
+    // de los `mtllib` (si no se encuentran, quedan los de por defecto)
+    pub materials: Vec<Material>,
+    // índice en `materials` de cada triángulo (None: antes del primer `usemtl`)
+    pub tri_material: Vec<Option<u32>>,
+    pub mtllibs:   Vec<String>,
+    // índice en `groups` de cada triángulo (para colorear por grupo sin buscar rangos)
+    pub tri_group: Vec<u32>,
+    // grupo de suavizado `s` de cada triángulo (0 = `s off`, caras planas)
+    pub smoothing: Vec<u32>,
+    // caja envolvente (min, max) de `positions`, calculada al cargar
+    pub bounds:    (Vec3, Vec3),
+    // caras `f` descartadas por índices fuera de rango (solo con `skip_bad_faces`)
+    pub skipped_faces: usize,
+    // triángulos de área (casi) nula descartados (solo con `drop_degenerate`)
+    pub dropped_degenerate: usize,
+    // texto de las líneas `# ...` (sin el `#`), hasta MAX_COMMENTS; no afecta a la geometría
+    pub comments:  Vec<String>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Cierra el grupo actual en `indices.len()`; los grupos vacíos se descartan
+    fn close_group(&mut self, name: &str, start: usize) {
+        let end = self.indices.len();
+        if end > start {
+            self.tri_group.resize(end / 3, self.groups.len() as u32);
+            self.groups.push((name.to_string(), start..end));
+        }
+    }
+
+    // Genera normales por vértice (sustituye las que hubiera): dentro de un grupo de
+    // suavizado se promedian las normales de cara (ponderadas por área) que comparten
+    // posición; con `s off` cada cara tiene la suya y las aristas quedan duras.
+    pub fn compute_vertex_normals(&mut self) {
+        let Mesh { positions, indices, smoothing, .. } = self;
+        let mut normals: Vec<Vec3> = Vec::new();
+        let mut shared: HashMap<(u32, u32), u32> = HashMap::new(); // (v, grupo) -> normal
+        for (t, tri) in indices.chunks_exact_mut(3).enumerate() {
+            let [a, b, c] = [tri[0].0, tri[1].0, tri[2].0].map(|v| positions[v as usize]);
+            let n = (b - a).cross(c - a); // sin normalizar: pesa por área
+            let group = smoothing.get(t).copied().unwrap_or(0);
+            let flat = normals.len() as u32;
+            if group == 0 { normals.push(Vec3::default()); }
+            for corner in tri {
+                let slot = if group == 0 {
+                    flat
+                } else {
+                    *shared.entry((corner.0, group)).or_insert_with(|| {
+                        normals.push(Vec3::default());
+                        (normals.len() - 1) as u32
+                    })
+                };
+                normals[slot as usize] = normals[slot as usize] + n;
+                corner.2 = Some(slot);
+            }
+        }
+        for n in &mut normals { *n = n.normalize(); }
+        self.normals = normals;
+    }
+
+    // Misma topología: mismo número de vértices y los mismos triángulos (índices idénticos)
+    pub fn same_topology(&self, o: &Mesh) -> bool {
+        self.positions.len() == o.positions.len() && self.indices == o.indices
+    }
+
+    // Posiciones interpoladas entre `a` (t=0) y `b` (t=1), que comparten topología con
+    // `self`; las normales también si ambas tienen las mismas. Índices, uv y grupos no cambian.
+    pub fn blend_from(&mut self, a: &Mesh, b: &Mesh, t: f32) {
+        for ((p, &pa), &pb) in self.positions.iter_mut().zip(&a.positions).zip(&b.positions) {
+            *p = lerp3(pa, pb, t);
+        }
+        if a.normals.len() == b.normals.len() {
+            for ((n, &na), &nb) in self.normals.iter_mut().zip(&a.normals).zip(&b.normals) {
+                *n = lerp3(na, nb, t).normalize();
+            }
+        }
+        self.bounds = aabb(&self.positions);
+    }
+
+    // Colapsa cada combinación distinta (v, vt, vn) en un vértice intercalado
+    // y devuelve un index buffer plano (3 por triángulo)
+    pub fn to_indexed(&self) -> IndexedMesh {
+        let mut out = IndexedMesh::default();
+        let mut seen: HashMap<(u32, Option<u32>, Option<u32>), u32> = HashMap::new();
+        for &key in &self.indices {
+            let idx = *seen.entry(key).or_insert_with(|| {
+                let (v, vt, vn) = key;
+                out.vertices.push(Vertex {
+                    position: self.positions[v as usize],
+                    texcoord: vt.map(|i| self.texcoords[i as usize]),
+                    normal:   vn.map(|i| self.normals[i as usize]),
+                });
+                (out.vertices.len() - 1) as u32
+            });
+            out.indices.push(idx);
+        }
+        out
+    }
+}
+
+// Material de un .mtl; lo que falte se deja en None y se usan los valores globales
+#[derive(Debug, Clone, Default)]
+pub struct Material {
+    pub name: String,
+    pub kd: Option<Vec3>, // difuso: color de relleno
+    pub ka: Option<Vec3>, // ambiente (None: el color difuso)
+    pub ks: Option<Vec3>, // especular
+    pub ns: Option<f32>,  // exponente especular
+    pub d: Option<f32>,   // opacidad (`d`, o 1 - `Tr`); None: opaco
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub texcoord: Option<Vec2>,
+    pub normal:   Option<Vec3>,
+}
+
+// Malla indexada "estilo GPU": un vértice por (v, vt, vn) distinto
+#[derive(Debug, Clone, Default)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices:  Vec<u32>,
+}
+
+/* ==== Lector OBJ mínimo (v, vt, vn, f) ==== */
+fn resolve_idx(obj_idx: i32, len: usize) -> Option<u32> {
+    if len == 0 { return None; }
+    let idx0 = if obj_idx > 0 {(obj_idx - 1) as isize} else {(len as isize) + (obj_idx as isize)};
+    if idx0 < 0 || (idx0 as usize) >= len { return None; }
+    Some(idx0 as u32)
+}
+
+fn parse_face_vertex(token: &str, vlen: usize, vtlen: usize, vnlen: usize)
+    -> Option<(u32, Option<u32>, Option<u32>)>
+{
+    let parts: Vec<&str> = token.split('/').collect();
+    let v  = resolve_idx(parts.first()?.parse::<i32>().ok()?, vlen)?;
+    let vt = if parts.len() >= 2 && !parts[1].is_empty() {
+        resolve_idx(parts[1].parse::<i32>().ok()?, vtlen)
+    } else { None };
+    let vn = if parts.len() >= 3 && !parts[2].is_empty() {
+        resolve_idx(parts[2].parse::<i32>().ok()?, vnlen)
+    } else { None };
+    Some((v, vt, vn))
+}
+
+fn triangulate_fan<T: Copy>(poly: &[T]) -> Vec<[T;3]> {
+    let mut tris = Vec::new();
+    for i in 2..poly.len() { tris.push([poly[0], poly[i-1], poly[i]]); }
+    tris
+}
+
+// Ear clipping sobre el plano de mejor ajuste (normal de Newell) para n-gonos
+// cóncavos. Devuelve índices dentro de `poly` con el mismo winding; triángulos y
+// quads van por el abanico.
+fn triangulate_earclip(poly: &[Vec3]) -> Vec<[usize;3]> {
+    let n = poly.len();
+    if n <= 4 { return triangulate_fan(&(0..n).collect::<Vec<_>>()); }
+
+    // Normal de Newell -> descartar el eje dominante para pasar a 2D
+    let (mut nx, mut ny, mut nz) = (0.0f32, 0.0f32, 0.0f32);
+    for i in 0..n {
+        let (Vec3(ax,ay,az), Vec3(bx,by,bz)) = (poly[i], poly[(i+1) % n]);
+        nx += (ay - by) * (az + bz);
+        ny += (az - bz) * (ax + bx);
+        nz += (ax - bx) * (ay + by);
+    }
+    let p2: Vec<(f32,f32)> = poly.iter().map(|&Vec3(x,y,z)| {
+        if nx.abs() >= ny.abs() && nx.abs() >= nz.abs() { (y, z) }
+        else if ny.abs() >= nz.abs() { (z, x) }
+        else { (x, y) }
+    }).collect();
+
+    // Orientación del polígono en 2D: los vértices convexos giran con el mismo signo
+    let area2: f32 = (0..n).map(|i| { let (a, b) = (p2[i], p2[(i+1) % n]); a.0*b.1 - b.0*a.1 }).sum();
+    let orient = if area2 >= 0.0 { 1.0 } else { -1.0 };
+    let cross = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (p2[a], p2[b], p2[c]);
+        ((b.0-a.0)*(c.1-a.1) - (b.1-a.1)*(c.0-a.0)) * orient
+    };
+
+    let mut rest: Vec<usize> = (0..n).collect();
+    let mut tris = Vec::with_capacity(n - 2);
+    while rest.len() > 3 {
+        let m = rest.len();
+        let ear = (0..m).find(|&k| {
+            let (a, b, c) = (rest[(k+m-1) % m], rest[k], rest[(k+1) % m]);
+            if cross(a, b, c) <= 0.0 { return false; } // reflejo o degenerado
+            // ningún otro vértice dentro (o sobre el borde) de la oreja
+            rest.iter().all(|&p| p == a || p == b || p == c
+                || cross(a, b, p) < 0.0 || cross(b, c, p) < 0.0 || cross(c, a, p) < 0.0)
+        });
+        match ear {
+            Some(k) => {
+                tris.push([rest[(k+m-1) % m], rest[k], rest[(k+1) % m]]);
+                rest.remove(k);
+            }
+            // polígono degenerado/autointersectado: lo que quede va en abanico
+            None => break,
+        }
+    }
+    tris.extend(triangulate_fan(&rest));
+    tris
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LoadOptions {
+    // una cara con un índice fuera de rango se salta (y se cuenta) en vez de abortar la carga
+    pub skip_bad_faces: bool,
+    // descarta triángulos con vértices repetidos o de área < DEGENERATE_AREA
+    pub drop_degenerate: bool,
+}
+
+const DEGENERATE_AREA: f32 = 1e-12;
+
+pub fn load_obj<P: AsRef<Path>>(path: P, opts: &LoadOptions) -> Result<Mesh, String> {
+    let file = File::open(path.as_ref()).map_err(|e| format!("No se pudo abrir: {e}"))?;
+    let mut raw = BufReader::new(file);
+    // .obj.gz: se detecta por el número mágico de gzip, no por la extensión. Multi porque
+    // pigz -i, bgzip o trozos .gz concatenados dejan varios miembros seguidos
+    let head = raw.fill_buf().map_err(|e| format!("No se pudo leer: {e}"))?;
+    let mut mesh = if head.starts_with(&GZIP_MAGIC) {
+        parse_obj(BufReader::new(MultiGzDecoder::new(raw)), opts)?
+    } else {
+        parse_obj(raw, opts)?
+    };
+
+    // `mtllib` es relativo al .obj; un .mtl que falte o no se lea no impide ver la malla
+    let dir = path.as_ref().parent().unwrap_or(Path::new(""));
+    for lib in &mesh.mtllibs {
+        let mtl_path = dir.join(lib);
+        let parsed = File::open(&mtl_path)
+            .map_err(|e| e.to_string())
+            .and_then(|f| parse_mtl(BufReader::new(f)));
+        match parsed {
+            Ok(defs) => for def in defs {
+                if let Some(m) = mesh.materials.iter_mut().find(|m| m.name == def.name) { *m = def; }
+            },
+            Err(e) => eprintln!("Aviso: no se pudo leer {}: {e}", mtl_path.display()),
+        }
+    }
+    Ok(mesh)
+}
+
+// Como `BufRead::lines` pero sin exigir UTF-8: lo inválido (p. ej. nombres en Windows-1252)
+// se sustituye por U+FFFD. La geometría es ASCII, así que los números no se ven afectados.
+fn lossy_lines<R: BufRead>(reader: R) -> impl Iterator<Item = std::io::Result<String>> {
+    reader.split(b'\n').map(|line| {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') { line.pop(); }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    })
+}
+
+// .mtl: newmtl, Kd, Ka, Ks y Ns. Los mapas (`map_*`, `bump`...) y el resto se ignoran, y un
+// valor mal escrito deja ese campo sin definir en lugar de fallar.
+pub fn parse_mtl<R: BufRead>(reader: R) -> Result<Vec<Material>, String> {
+    let mut materials: Vec<Material> = Vec::new();
+    for (lineno, line) in lossy_lines(reader).enumerate() {
+        let line = line.map_err(|e| format!("Error L{}: {e}", lineno+1))?;
+        let mut it = line.split_whitespace();
+        let tag = it.next().unwrap_or_default();
+        if tag == "newmtl" {
+            materials.push(Material { name: it.collect::<Vec<_>>().join(" "), ..Material::default() });
+            continue;
+        }
+        let Some(m) = materials.last_mut() else { continue };
+        let xs: Vec<f32> = it.map_while(|x| x.parse().ok()).collect();
+        // `Kd r` sin g y b es un gris
+        let rgb = || match xs[..] {
+            [r, g, b, ..] => Some(Vec3(r, g, b)),
+            [k] => Some(Vec3(k, k, k)),
+            _ => None,
+        };
+        match tag {
+            "Kd" => m.kd = rgb(),
+            "Ka" => m.ka = rgb(),
+            "Ks" => m.ks = rgb(),
+            "Ns" => m.ns = xs.first().copied(),
+            "d"  => m.d = xs.first().copied(),
+            // `Tr` es la transparencia (1 - d); si el archivo trae `d` también, manda `d`
+            "Tr" if m.d.is_none() => m.d = xs.first().map(|tr| 1.0 - tr),
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+// Comentarios que se guardan por malla (un archivo con millones de `#` no dispara la memoria)
+const MAX_COMMENTS: usize = 256;
+
+// Parser de OBJ sobre cualquier fuente de líneas (archivo, memoria, stdin...)
+pub fn parse_obj<R: BufRead>(reader: R, opts: &LoadOptions) -> Result<Mesh, String> {
+    let mut mesh = Mesh::new();
+    let mut group_name = String::new();
+    let mut group_start = 0usize;
+    let mut smoothing_group = 0u32; // sin `s`, caras planas
+    let mut material: Option<u32> = None;
+
+    let mut lines = lossy_lines(reader).enumerate();
+    while let Some((lineno, line_res)) = lines.next() {
+        let mut line = line_res.map_err(|e| format!("Error L{}: {e}", lineno+1))?;
+        // `\` al final continúa en la línea siguiente; `lineno` sigue siendo el de la primera.
+        // Los comentarios no se continúan para no tragarse una línea de datos.
+        while !line.trim_start().starts_with('#') && line.trim_end().ends_with('\\') {
+            line.truncate(line.trim_end().len() - 1);
+            line.push(' ');
+            match lines.next() {
+                Some((n, next)) => line.push_str(&next.map_err(|e| format!("Error L{}: {e}", n+1))?),
+                None => break,
+            }
+        }
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix('#') {
+            let text = text.trim();
+            if !text.is_empty() && mesh.comments.len() < MAX_COMMENTS { mesh.comments.push(text.to_string()); }
+            continue;
+        }
+        if line.is_empty() { continue; }
+
+        let mut it = line.split_whitespace();
+        let tag = it.next().unwrap_or_default();
+
+        match tag {
+            "v" => {
+                // solo cuentan los números iniciales: lo que siga (un `#` pegado, texto) se ignora
+                let xs: Vec<f32> = it.map_while(|x| x.parse().ok()).collect();
+                if xs.len() < 3 { return Err(format!("v inválido L{}", lineno+1)); }
+                let mut p = Vec3(xs[0], xs[1], xs[2]);
+                // `v x y z w` (racional): con 4 o 5 componentes la cuarta es w; con 6+ son colores
+                if let Some(&w) = xs.get(3).filter(|_| xs.len() < 6) && w != 1.0 && w != 0.0 {
+                    p = Vec3(p.0 / w, p.1 / w, p.2 / w);
+                }
+                mesh.positions.push(p);
+                if xs.len() >= 6 {
+                    // primer vértice con color: los anteriores quedan en gris neutro
+                    mesh.colors.resize(mesh.positions.len() - 1, Vec3(0.5, 0.5, 0.5));
+                    mesh.colors.push(Vec3(xs[3], xs[4], xs[5]));
+                } else if !mesh.colors.is_empty() {
+                    mesh.colors.push(Vec3(0.5, 0.5, 0.5));
+                }
+            }
+            "vt" => {
+                let xs: Vec<f32> = it.map_while(|x| x.parse().ok()).collect();
+                if xs.len() < 2 { return Err(format!("vt inválido L{}", lineno+1)); }
+                mesh.texcoords.push(Vec2(xs[0], xs[1]));
+                if let Some(&w) = xs.get(2) {
+                    // mismo esquema que los colores: se rellena con 0 hasta el primer vt 3D
+                    mesh.texcoord_w.resize(mesh.texcoords.len() - 1, 0.0);
+                    mesh.texcoord_w.push(w);
+                } else if !mesh.texcoord_w.is_empty() {
+                    mesh.texcoord_w.push(0.0);
+                }
+            }
+            "vn" => {
+                let xs: Vec<f32> = it.map_while(|x| x.parse().ok()).collect();
+                if xs.len() < 3 { return Err(format!("vn inválido L{}", lineno+1)); }
+                mesh.normals.push(Vec3(xs[0], xs[1], xs[2]));
+            }
+            "f" => {
+                let face_tokens: Vec<String> = it.map(|s| s.to_string()).collect();
+                if face_tokens.len() < 3 { return Err(format!("f < 3 vértices L{}", lineno+1)); }
+                let poly: Option<Vec<_>> = face_tokens.iter()
+                    .map(|t| parse_face_vertex(t, mesh.positions.len(), mesh.texcoords.len(), mesh.normals.len()))
+                    .collect();
+                let poly = match poly {
+                    Some(p) => p,
+                    None if opts.skip_bad_faces => { mesh.skipped_faces += 1; continue; }
+                    None => return Err(format!("Índice inválido L{}", lineno+1)),
+                };
+                let pts: Vec<Vec3> = poly.iter().map(|&(v, _, _)| mesh.positions[v as usize]).collect();
+                for tri in triangulate_earclip(&pts) {
+                    if opts.drop_degenerate {
+                        let [a, b, c] = tri.map(|k| poly[k].0);
+                        let [pa, pb, pc] = tri.map(|k| pts[k]);
+                        if a == b || b == c || a == c || 0.5 * (pb - pa).cross(pc - pa).length() < DEGENERATE_AREA {
+                            mesh.dropped_degenerate += 1;
+                            continue;
+                        }
+                    }
+                    mesh.indices.push(poly[tri[0]]);
+                    mesh.indices.push(poly[tri[1]]);
+                    mesh.indices.push(poly[tri[2]]);
+                    mesh.smoothing.push(smoothing_group);
+                    mesh.tri_material.push(material);
+                }
+            }
+            // `l v1 v2 v3...` (o `v/vt`): polilínea, se guarda como segmentos consecutivos
+            "l" => {
+                let vs: Vec<u32> = it
+                    .map(|t| t.split('/').next().and_then(|v| v.parse().ok()).and_then(|v| resolve_idx(v, mesh.positions.len())))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| format!("Índice inválido L{}", lineno+1))?;
+                if vs.len() < 2 { return Err(format!("l < 2 vértices L{}", lineno+1)); }
+                mesh.lines.extend(vs.windows(2).map(|w| (w[0], w[1])));
+            }
+            // `s 1`, `s 2`... agrupan; `s off` / `s 0` desactivan el suavizado
+            "s" => {
+                smoothing_group = match it.next() {
+                    Some("off") | None => 0,
+                    Some(g) => g.parse().map_err(|_| format!("s inválido L{}", lineno+1))?,
+                };
+            }
+            "usemtl" => {
+                let name = it.collect::<Vec<_>>().join(" ");
+                let idx = match mesh.materials.iter().position(|m| m.name == name) {
+                    Some(i) => i,
+                    None => {
+                        mesh.materials.push(Material { name, ..Material::default() });
+                        mesh.materials.len() - 1
+                    }
+                };
+                material = Some(idx as u32);
+            }
+            "mtllib" => mesh.mtllibs.extend(it.map(str::to_string)),
+            "o" | "g" => {
+                mesh.close_group(&group_name, group_start);
+                group_name = it.collect::<Vec<_>>().join(" ");
+                group_start = mesh.indices.len();
+            }
+            _ => {}
+        }
+    }
+    mesh.close_group(&group_name, group_start);
+    mesh.bounds = aabb(&mesh.positions);
+
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn parse(src: &str) -> Mesh {
+        parse_obj(Cursor::new(src), &LoadOptions::default()).unwrap()
+    }
+
+    // cubo de lado 2 con índices relativos: se puede repetir en el mismo archivo
+    const CUBE: &str = "\
+v -1 -1 -1\nv 1 -1 -1\nv 1 1 -1\nv -1 1 -1\nv -1 -1 1\nv 1 -1 1\nv 1 1 1\nv -1 1 1
+f -8 -5 -6 -7\nf -4 -3 -2 -1\nf -8 -7 -3 -4\nf -5 -1 -2 -6\nf -8 -4 -1 -5\nf -7 -6 -2 -3
+";
+
+    #[test]
+    fn v_ignora_tokens_que_no_son_numeros() {
+        let m = parse("v 0 0 0 foo\nv 1 2 3 #c\nv 2 4 6 2 # w\n");
+        assert_eq!(m.positions, vec![Vec3(0.0, 0.0, 0.0), Vec3(1.0, 2.0, 3.0), Vec3(1.0, 2.0, 3.0)]);
+        assert!(m.colors.is_empty());
+    }
+
+    #[test]
+    fn vt_ignora_tokens_que_no_son_numeros() {
+        let m = parse("vt 0 0 x\nvt 0.5 1 #c\nvt 1 1 0.25\n");
+        assert_eq!(m.texcoords, vec![Vec2(0.0, 0.0), Vec2(0.5, 1.0), Vec2(1.0, 1.0)]);
+        assert_eq!(m.texcoord_w, vec![0.0, 0.0, 0.25]);
+    }
+
+    #[test]
+    fn color_de_vertice_con_comentario_detras() {
+        let m = parse("v 0 0 0\nv 1 2 3 1 0 0.5 #rojo\nv 1 1 1 0.5 0.5 x\n");
+        assert_eq!(m.colors[..2], [Vec3(0.5, 0.5, 0.5), Vec3(1.0, 0.0, 0.5)]);
+        assert_eq!(m.positions.len(), 3);
+        assert!(parse_obj(Cursor::new("v 1 2\n"), &LoadOptions::default()).is_err());
+    }
+
+    #[test]
+    fn dos_cubos_dos_grupos() {
+        let m = parse(&format!("o uno\n{CUBE}o dos\n{CUBE}"));
+        assert_eq!(m.groups, vec![("uno".to_string(), 0..36), ("dos".to_string(), 36..72)]);
+        assert_eq!(m.groups.iter().map(|(_, r)| r.len() / 3).collect::<Vec<_>>(), [12, 12]);
+        assert_eq!(m.tri_group, [[0; 12], [1; 12]].concat());
+    }
+
+    #[test]
+    fn pentagono_concavo_sin_solapes() {
+        // pentágono con muesca en (1,0.5): el abanico desde el primer vértice se saldría
+        let m = parse("v 0 0 0\nv 2 0 0\nv 2 2 0\nv 1 0.5 0\nv 0 2 0\nf 1 2 3 4 5\n");
+        assert_eq!(m.indices.len(), 9);
+        // área con signo en z: todas positivas (mismo winding) y sumando la del polígono,
+        // así que no se solapan ni se salen
+        let areas: Vec<f32> = m.indices.chunks_exact(3).map(|t| {
+            let [a, b, c] = [t[0].0, t[1].0, t[2].0].map(|v| m.positions[v as usize]);
+            0.5 * (b - a).cross(c - a).2
+        }).collect();
+        assert!(areas.iter().all(|&a| a > 0.0), "{areas:?}");
+        assert!((areas.iter().sum::<f32>() - 2.5).abs() < 1e-5, "{areas:?}");
+    }
+
+    #[test]
+    fn obj_gz_por_load_obj() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        // el nombre no acaba en .gz: se reconoce por el número mágico. Dos miembros gzip
+        // seguidos (como los de bgzip): vértices en uno y caras en otro
+        let path = std::env::temp_dir().join(format!("lector_obj_test_{}.obj", std::process::id()));
+        let (verts, faces) = CUBE.split_at(CUBE.find('f').unwrap());
+        let mut file = File::create(&path).unwrap();
+        for member in [verts, faces] {
+            let mut gz = GzEncoder::new(&mut file, Compression::default());
+            gz.write_all(member.as_bytes()).unwrap();
+            gz.finish().unwrap();
+        }
+        drop(file);
+
+        let m = load_obj(&path, &LoadOptions::default());
+        std::fs::remove_file(&path).unwrap();
+        let m = m.unwrap();
+        assert_eq!(m.positions, parse(CUBE).positions);
+        assert_eq!(m.indices.len(), 36);
+    }
+
+    #[test]
+    fn cara_continuada_con_barra() {
+        let m = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 \\\n  3 \\\n 4\n");
+        assert_eq!(m.indices.len(), 6);
+        // el error se cuenta en la primera línea física de la cara (L5), no en la última
+        let err = parse_obj(Cursor::new("v 0 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 \\\n 3 \\\n 9\n"), &LoadOptions::default());
+        assert_eq!(err.unwrap_err(), "Índice inválido L5");
+    }
+
+    #[test]
+    fn cubo_con_s_off_normales_planas() {
+        let mut m = parse(&format!("s off\n{CUBE}"));
+        m.compute_vertex_normals();
+        assert_eq!(m.normals.len(), 12); // una por triángulo, sin compartir entre caras
+        for t in m.indices.chunks_exact(3) {
+            let [a, b, c] = [t[0].0, t[1].0, t[2].0].map(|v| m.positions[v as usize]);
+            let face = (b - a).cross(c - a).normalize();
+            for corner in t {
+                let n = m.normals[corner.2.unwrap() as usize];
+                assert_eq!(n, face);
+                // de eje: ±1 en una componente y 0 en las otras
+                assert_eq!(n.0.abs() + n.1.abs() + n.2.abs(), 1.0, "{n:?}");
+            }
+        }
+        // con un grupo de suavizado las esquinas sí se comparten: una normal por vértice
+        let mut m = parse(&format!("s 1\n{CUBE}"));
+        m.compute_vertex_normals();
+        assert_eq!(m.normals.len(), 8);
+    }
+
+    #[test]
+    fn descarta_caras_degeneradas() {
+        // una buena, una con índice repetido y una con los tres vértices alineados
+        let src = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nf 1 2 3\nf 1 1 2\nf 1 2 4\n";
+        let m = parse_obj(Cursor::new(src), &LoadOptions { drop_degenerate: true, ..LoadOptions::default() }).unwrap();
+        assert_eq!(m.dropped_degenerate, 2);
+        assert_eq!(m.indices.len(), 3);
+        assert_eq!(m.smoothing.len(), 1);
+        // sin la opción se quedan todas
+        let m = parse(src);
+        assert_eq!((m.dropped_degenerate, m.indices.len()), (0, 9));
+    }
+
+    #[test]
+    fn cuatro_formas_de_vertice_de_cara() {
+        let head = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nvn 0 0 -1\n";
+        let tri = |face: &str| parse(&format!("{head}{face}\n")).indices;
+        assert_eq!(tri("f 1 2 3"), [(0, None, None), (1, None, None), (2, None, None)]);
+        assert_eq!(tri("f 1/3 2/2 3/1"), [(0, Some(2), None), (1, Some(1), None), (2, Some(0), None)]);
+        assert_eq!(tri("f 1//2 2//1 3//2"), [(0, None, Some(1)), (1, None, Some(0)), (2, None, Some(1))]);
+        assert_eq!(tri("f 1/1/1 2/2/2 -1/-1/-1"), [(0, Some(0), Some(0)), (1, Some(1), Some(1)), (2, Some(2), Some(1))]);
+        // v/vt/vn, v/vt y v//vn en una misma cara
+        let m = parse(&format!("{head}vn 1 0 0\nf 1/1/1 2/2 3//3\n"));
+        assert_eq!(m.indices, [(0, Some(0), Some(0)), (1, Some(1), None), (2, None, Some(2))]);
+    }
+}
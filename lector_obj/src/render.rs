@@ -0,0 +1,1096 @@
+//! Framebuffer con z-buffer, raster de triángulos, iluminación y overlays.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::math::{
+    aabb, aspect, clip_triangle_near, focal_length, lerp3, ndc_to_screen, project_orthographic_to_screen,
+    project_perspective_to_screen, view_matrix, CamVertex, Mat4,
+};
+use crate::mesh::{Material, Mesh, Vec2, Vec3};
+
+/* ==== Framebuffer con z-buffer y ventana ==== */
+pub struct Frame {
+    pub w: usize,
+    pub h: usize,
+    pub color: Vec<u32>, // 0x00RRGGBB
+    pub depth: Vec<f32>, // z-buffer (menor = más cerca)
+}
+
+impl Frame {
+    pub fn new(w: usize, h: usize) -> Self {
+        Self { w, h, color: vec![DEFAULT_BG; w*h], depth: vec![f32::INFINITY; w*h] }
+    }
+    pub fn clear(&mut self, rgb: u32) {
+        self.color.fill(rgb);
+        self.depth.fill(f32::INFINITY);
+    }
+
+    // Como `clear`, pero cada fila mezcla `top` (fila 0) y `bottom` (última fila)
+    pub fn clear_gradient(&mut self, top: u32, bottom: u32) {
+        let (top, bottom) = (unpack_rgb(top), unpack_rgb(bottom));
+        let last = self.h.saturating_sub(1).max(1) as f32;
+        for (y, row) in self.color.chunks_exact_mut(self.w.max(1)).enumerate() {
+            row.fill(pack_rgb(lerp3(top, bottom, y as f32 / last)));
+        }
+        self.depth.fill(f32::INFINITY);
+    }
+
+    // Realoja los buffers solo si cambia el tamaño
+    pub fn resize(&mut self, w: usize, h: usize) {
+        if (self.w, self.h) != (w, h) { *self = Frame::new(w, h); }
+    }
+
+    // Sustituye el color por la profundidad en gris: la más cercana del frame en blanco,
+    // la más lejana en negro; el fondo (sin profundidad) queda negro
+    pub fn depth_to_color(&mut self) {
+        let finite = self.depth.iter().copied().filter(|z| z.is_finite());
+        let (lo, hi) = finite.fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), z| (lo.min(z), hi.max(z)));
+        let range = (hi - lo).max(1e-6);
+        for (c, &z) in self.color.iter_mut().zip(&self.depth) {
+            *c = if z.is_finite() {
+                let g = (255.0 * (1.0 - (z - lo) / range)).round() as u32;
+                (g << 16) | (g << 8) | g
+            } else {
+                0x000000
+            };
+        }
+    }
+
+    // Reduce un frame de (2w, 2h) a `out` (w, h): color = media de cada bloque 2x2,
+    // depth = la más cercana del bloque (no se mezclan profundidades)
+    pub fn downsample_2x_into(&self, out: &mut Frame) {
+        debug_assert!(self.w == 2*out.w && self.h == 2*out.h);
+        for y in 0..out.h {
+            for x in 0..out.w {
+                let i0 = 2*y*self.w + 2*x;
+                let block = [i0, i0 + 1, i0 + self.w, i0 + self.w + 1];
+                let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+                let mut z = f32::INFINITY;
+                for i in block {
+                    let c = self.color[i];
+                    r += (c >> 16) & 0xFF;
+                    g += (c >> 8) & 0xFF;
+                    b += c & 0xFF;
+                    z = z.min(self.depth[i]);
+                }
+                out.color[y*out.w + x] = ((r / 4) << 16) | ((g / 4) << 8) | (b / 4);
+                out.depth[y*out.w + x] = z;
+            }
+        }
+    }
+}
+
+// Franja horizontal de filas [y0, y1) del frame; cada hilo del raster paralelo
+// es dueño de la suya, así el z-buffer se comparte sin locks.
+pub struct Band<'a> {
+    pub w: usize,
+    pub y0: usize,
+    pub y1: usize,
+    pub color: &'a mut [u32],
+    pub depth: &'a mut [f32],
+}
+
+impl Band<'_> {
+    // y en coordenadas del frame completo
+    #[inline]
+    pub fn put_pixel_z(&mut self, x: i32, y: i32, z: f32, rgb: u32) {
+        if x<0 || y<0 {return;}
+        let (x, y) = (x as usize, y as usize);
+        if x>=self.w || y<self.y0 || y>=self.y1 {return;}
+        let idx = (y-self.y0)*self.w + x;
+        if z < self.depth[idx] {
+            self.depth[idx] = z;
+            self.color[idx] = rgb;
+        }
+    }
+
+    // Como put_pixel_z pero mezcla `rgb` sobre lo que haya con opacidad `alpha` y no escribe
+    // profundidad (lo transparente no tapa lo que se pinte detrás después)
+    #[inline]
+    pub fn blend_pixel_z(&mut self, x: i32, y: i32, z: f32, rgb: u32, alpha: f32) {
+        if x<0 || y<0 {return;}
+        let (x, y) = (x as usize, y as usize);
+        if x>=self.w || y<self.y0 || y>=self.y1 {return;}
+        let idx = (y-self.y0)*self.w + x;
+        if z < self.depth[idx] {
+            self.color[idx] = pack_rgb(lerp3(unpack_rgb(self.color[idx]), unpack_rgb(rgb), alpha));
+        }
+    }
+}
+
+/* ==== Texturas ==== */
+pub struct Texture {
+    pub w: usize,
+    pub h: usize,
+    pub texels: Vec<u32>, // 0xAARRGGBB, fila 0 = arriba
+}
+
+impl Texture {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("No se pudo abrir la textura {}: {e}", path.display()))?
+            .to_rgba8();
+        let (w, h) = (img.width() as usize, img.height() as usize);
+        let texels = img.pixels()
+            .map(|p| { let [r, g, b, a] = p.0; u32::from_be_bytes([a, r, g, b]) })
+            .collect();
+        Ok(Self { w, h, texels })
+    }
+
+    // Vecino más cercano; UV con wrap (repetición), v = 0 abajo como en OBJ
+    #[inline]
+    pub fn sample_nearest(&self, u: f32, v: f32) -> u32 {
+        let (u, v) = (u - u.floor(), v - v.floor());
+        let x = ((u * self.w as f32) as usize).min(self.w - 1);
+        let y = (((1.0 - v) * self.h as f32) as usize).min(self.h - 1);
+        self.texels[y*self.w + x] & 0x00FF_FFFF
+    }
+
+    // Mezcla de los 4 texels vecinos (centros en (i + 0.5) / w); el wrap es el mismo
+    // que en `sample_nearest`, así que en los bordes se mezcla con el lado opuesto
+    pub fn sample_bilinear(&self, u: f32, v: f32) -> u32 {
+        let fx = (u - u.floor()) * self.w as f32 - 0.5;
+        let fy = (1.0 - (v - v.floor())) * self.h as f32 - 0.5;
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let wrap = |i: f32, n: usize| (i as i64).rem_euclid(n as i64) as usize;
+        let (xa, xb) = (wrap(x0, self.w), wrap(x0 + 1.0, self.w));
+        let (ya, yb) = (wrap(y0, self.h), wrap(y0 + 1.0, self.h));
+        let texel = |x: usize, y: usize| unpack_rgb(self.texels[y*self.w + x]);
+        let top = lerp3(texel(xa, ya), texel(xb, ya), tx);
+        let bottom = lerp3(texel(xa, yb), texel(xb, yb), tx);
+        pack_rgb(lerp3(top, bottom, ty))
+    }
+}
+
+/* ==== Raster de triángulo con z (bary) ==== */
+#[inline] fn edge(ax:f32, ay:f32, bx:f32, by:f32, px:f32, py:f32) -> f32 {
+    (px-ax)*(by-ay) - (py-ay)*(bx-ax)
+}
+
+// RGB en [0,1] -> 0x00RRGGBB
+#[inline]
+pub fn pack_rgb(Vec3(r,g,b): Vec3) -> u32 {
+    let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u32;
+    (c(r) << 16) | (c(g) << 8) | c(b)
+}
+
+#[inline]
+pub fn unpack_rgb(c: u32) -> Vec3 {
+    let f = |s: u32| ((c >> s) & 0xFF) as f32 / 255.0;
+    Vec3(f(16), f(8), f(0))
+}
+
+/* ==== Iluminación (espacio cámara) ==== */
+const AMBIENT: f32 = 0.2;
+const DIFFUSE: f32 = 0.8;
+const SPECULAR: f32 = 0.5;           // peso del brillo blanco
+pub const DEFAULT_SHININESS: f32 = 32.0;
+// hacia la luz: arriba a la izquierda y por delante del modelo (cámara en origen mirando +Z)
+const LIGHT_DIR: Vec3 = Vec3(-0.4, 0.6, -0.7);
+// Wireframe baricéntrico: ancho del borde en coordenadas baricéntricas y brillo que queda en la arista
+const WIRE_EDGE: f32 = 0.03;
+const WIRE_DARK: f32 = 0.15;
+
+// Posiciones y normales de un triángulo en espacio cámara, para sombrear por píxel
+#[derive(Clone, Copy)]
+pub struct Surface {
+    pub p: [Vec3;3],
+    pub n: [Vec3;3],
+    pub phong: Phong,
+}
+
+// Phong del material del triángulo; lo que sea None usa los valores globales
+#[derive(Clone, Copy, Default)]
+pub struct Phong {
+    pub ka: Option<Vec3>, // None: el color base
+    pub ks: Option<Vec3>, // None: blanco * SPECULAR
+    pub ns: Option<f32>,  // None: --shininess
+}
+
+impl Phong {
+    pub fn of(mat: &Material) -> Phong {
+        Phong { ka: mat.ka, ks: mat.ks, ns: mat.ns }
+    }
+}
+
+// Parámetros que el raster comparte entre todos los triángulos del frame
+#[derive(Clone, Copy)]
+pub struct RasterParams {
+    pub perspective_correct: bool,
+    pub two_sided: bool,         // voltea la normal de las caras que miran hacia atrás
+    pub light: Vec3,             // LIGHT_DIR normalizada
+    pub shininess: Option<f32>,  // Some: término especular de Phong
+    pub bilinear: bool,          // filtrado de textura; si no, vecino más cercano
+    pub wireframe: bool,         // oscurecer cerca de las aristas (min b_i < WIRE_EDGE)
+    pub matcap: bool,            // color por la normal (shade_matcap) en lugar de shade_lit
+}
+
+impl RasterParams {
+    pub fn new(cam: &Camera, opts: &RenderOptions) -> Self {
+        Self {
+            // en ortográfica lo afín ya es exacto
+            perspective_correct: !cam.ortho && !opts.affine,
+            two_sided: !opts.cull_backfaces,
+            light: LIGHT_DIR.normalize(),
+            shininess: opts.specular.then_some(opts.shininess),
+            bilinear: opts.bilinear,
+            wireframe: opts.wireframe,
+            matcap: opts.matcap,
+        }
+    }
+}
+
+// Lambert + Phong: ka * ambiente + base * difusa + ks * especular (ka = base y
+// ks = blanco * SPECULAR si el material no dice otra cosa)
+pub fn shade_lit(base: Vec3, n: Vec3, p: Vec3, phong: &Phong, params: &RasterParams) -> Vec3 {
+    let view = p.scale(-1.0).normalize(); // de la superficie a la cámara
+    let mut n = n.normalize();
+    if params.two_sided && n.dot(view) < 0.0 { n = n.scale(-1.0); }
+    let l = params.light;
+    let ndotl = n.dot(l).max(0.0);
+    let mut c = phong.ka.unwrap_or(base).scale(AMBIENT) + base.scale(DIFFUSE * ndotl);
+    if let Some(shininess) = params.shininess.filter(|_| ndotl > 0.0) {
+        let r = n.scale(2.0 * n.dot(l)) - l; // reflejo de la luz alrededor de n
+        let spec = r.dot(view).max(0.0).powf(phong.ns.unwrap_or(shininess));
+        let Vec3(sr, sg, sb) = phong.ks.unwrap_or(Vec3(SPECULAR, SPECULAR, SPECULAR));
+        c = c + Vec3(sr * spec, sg * spec, sb * spec);
+    }
+    c
+}
+
+const MATCAP_SAT: f32 = 0.8; // saturación en la silueta
+
+// Matcap: el color sale solo de la normal en cámara, sin luz ni color base. El tono es la
+// dirección de (n.x, n.y) y la saturación su longitud: de cara a la cámara casi blanco,
+// hacia la silueta cada orientación con su color.
+pub fn shade_matcap(n: Vec3, p: Vec3, params: &RasterParams) -> Vec3 {
+    let mut n = n.normalize();
+    if params.two_sided && n.dot(p) > 0.0 { n = n.scale(-1.0); }
+    let hue = n.1.atan2(n.0) / std::f32::consts::TAU + 0.5;
+    let sat = MATCAP_SAT * (n.0*n.0 + n.1*n.1).sqrt().min(1.0);
+    // hsv -> rgb con v = 1: cada canal es la misma rampa del tono desfasada un tercio
+    let channel = |offset: f32| {
+        let k = (hue + offset).fract() * 6.0;
+        let c = ((k - 3.0).abs() - 1.0).clamp(0.0, 1.0);
+        1.0 - sat * (1.0 - c)
+    };
+    Vec3(channel(0.0), channel(2.0/3.0), channel(1.0/3.0))
+}
+
+// Cómo se pinta un triángulo
+#[derive(Clone, Copy)]
+pub enum Shade<'a> {
+    Flat(u32),                      // 0x00RRGGBB
+    Vertex([Vec3;3]),               // color por vértice, interpolado con las baricéntricas
+    Texture(&'a Texture, [Vec2;3]), // UV por vértice, muestreo por píxel
+}
+
+// tri.v: (x,y,z_cam) – z_cam para z-buffer. Solo toca las filas de `fb`.
+// `perspective_correct`: interpola los atributos (color, UV, normal) como a/z y 1/z y
+// vuelve a dividir por píxel; en ortográfica la interpolación lineal ya es exacta.
+// Con `surface` el color base se ilumina por píxel con la normal interpolada.
+// Con `alpha` < 1 se mezcla sobre el frame sin escribir profundidad (blend_pixel_z).
+pub fn fill_triangle_z(fb: &mut Band, tri: &ScreenTri, params: &RasterParams) {
+    let [v0, v1, v2] = tri.v;
+    let (shade, surface, alpha) = (&tri.shade, tri.surface.as_ref(), tri.alpha);
+    let perspective_correct = params.perspective_correct;
+    let (x0,y0,z0) = v0; let (x1,y1,z1) = v1; let (x2,y2,z2) = v2;
+
+    let min_x = x0.min(x1).min(x2).floor().max(0.0) as i32;
+    let max_x = x0.max(x1).max(x2).ceil().min((fb.w-1) as f32) as i32;
+    let min_y = y0.min(y1).min(y2).floor().max(fb.y0 as f32) as i32;
+    let max_y = y0.max(y1).max(y2).ceil().min((fb.y1-1) as f32) as i32;
+    if min_y > max_y { return; }
+
+    let area = edge(x0,y0, x1,y1, x2,y2);
+    if area == 0.0 { return; }
+
+    // pesos de atributos: con corrección de perspectiva b_i/z_i renormalizados por Σ b_i/z_i
+    let (iz0, iz1, iz2) = (1.0/z0, 1.0/z1, 1.0/z2);
+    let attr_weights = |b0: f32, b1: f32, b2: f32| {
+        if !perspective_correct { return (b0, b1, b2); }
+        let (q0, q1, q2) = (b0*iz0, b1*iz1, b2*iz2);
+        let inv = 1.0 / (q0 + q1 + q2);
+        (q0*inv, q1*inv, q2*inv)
+    };
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let px = x as f32 + 0.5;
+            let py = y as f32 + 0.5;
+
+            let w0 = edge(x1,y1, x2,y2, px,py);
+            let w1 = edge(x2,y2, x0,y0, px,py);
+            let w2 = edge(x0,y0, x1,y1, px,py);
+
+            if (w0>=0.0 && w1>=0.0 && w2>=0.0 && area>0.0) ||
+               (w0<=0.0 && w1<=0.0 && w2<=0.0 && area<0.0) {
+
+                // bary normalizadas
+                let b0 = w0/area;
+                let b1 = w1/area;
+                let b2 = w2/area;
+
+                // z_cam interpolada (correcto para z-buffer)
+                let z = b0*z0 + b1*z1 + b2*z2;
+
+                let rgb = match shade {
+                    Shade::Flat(rgb) => *rgb,
+                    Shade::Vertex([c0, c1, c2]) => {
+                        let (a0, a1, a2) = attr_weights(b0, b1, b2);
+                        pack_rgb(Vec3(
+                            a0*c0.0 + a1*c1.0 + a2*c2.0,
+                            a0*c0.1 + a1*c1.1 + a2*c2.1,
+                            a0*c0.2 + a1*c1.2 + a2*c2.2,
+                        ))
+                    }
+                    Shade::Texture(tex, [t0, t1, t2]) => {
+                        let (a0, a1, a2) = attr_weights(b0, b1, b2);
+                        let (u, v) = (a0*t0.0 + a1*t1.0 + a2*t2.0, a0*t0.1 + a1*t1.1 + a2*t2.1);
+                        if params.bilinear { tex.sample_bilinear(u, v) } else { tex.sample_nearest(u, v) }
+                    }
+                };
+                let rgb = match surface {
+                    Some(Surface { p: [p0, p1, p2], n: [n0, n1, n2], phong }) => {
+                        let (a0, a1, a2) = attr_weights(b0, b1, b2);
+                        let n = n0.scale(a0) + n1.scale(a1) + n2.scale(a2);
+                        let p = p0.scale(a0) + p1.scale(a1) + p2.scale(a2);
+                        if params.matcap {
+                            pack_rgb(shade_matcap(n, p, params))
+                        } else {
+                            pack_rgb(shade_lit(unpack_rgb(rgb), n, p, phong, params))
+                        }
+                    }
+                    None => rgb,
+                };
+                // en el mismo pase que el relleno: rampa de WIRE_DARK (arista) a 1 (a WIRE_EDGE)
+                let rgb = if params.wireframe && b0.min(b1).min(b2) < WIRE_EDGE {
+                    let k = b0.min(b1).min(b2).max(0.0) / WIRE_EDGE;
+                    pack_rgb(unpack_rgb(rgb).scale(WIRE_DARK + (1.0 - WIRE_DARK) * k))
+                } else {
+                    rgb
+                };
+                if alpha < 1.0 { fb.blend_pixel_z(x, y, z, rgb, alpha); } else { fb.put_pixel_z(x, y, z, rgb); }
+            }
+        }
+    }
+}
+
+// Triángulo ya proyectado, listo para rasterizar
+pub struct ScreenTri<'a> {
+    pub v: [(f32,f32,f32);3], // (x,y,z_cam)
+    pub shade: Shade<'a>,
+    pub surface: Option<Surface>, // None: sin iluminación
+    pub alpha: f32,               // opacidad del material (1: opaco)
+}
+
+// Backface culling 2D (si `cull`); si sobrevive se encola para el raster.
+// Sin culling solo se descartan los degenerados: fill_triangle_z acepta ambos sentidos.
+pub fn push_triangle<'a>(
+    tris: &mut Vec<ScreenTri<'a>>,
+    v: [(f32,f32,f32);3],
+    shade: Shade<'a>,
+    surface: Option<Surface>,
+    alpha: f32,
+    cull: bool,
+) {
+    let [v0, v1, v2] = v;
+    let ax = v1.0 - v0.0; let ay = v1.1 - v0.1;
+    let bx = v2.0 - v0.0; let by = v2.1 - v0.1;
+    let cross = ax*by - ay*bx;
+    if cross == 0.0 || (cull && cross < 0.0) { return; }
+
+    tris.push(ScreenTri { v, shade, surface, alpha });
+}
+
+const BAND_ROWS: usize = 16;
+
+fn raster_band(band: &mut Band, tris: &[ScreenTri], bin: &[u32], params: &RasterParams) {
+    for &i in bin {
+        fill_triangle_z(band, &tris[i as usize], params);
+    }
+}
+
+// Reparte cada triángulo en las franjas que cubre su bbox vertical, para que
+// cada franja recorra solo lo suyo y no la malla entera
+fn bin_triangles(tris: &[ScreenTri], h: usize) -> Vec<Vec<u32>> {
+    let n_bands = h.div_ceil(BAND_ROWS);
+    let mut bins = vec![Vec::new(); n_bands];
+    for (i, t) in tris.iter().enumerate() {
+        let min_y = t.v[0].1.min(t.v[1].1).min(t.v[2].1).floor().max(0.0) as usize;
+        let max_y = t.v[0].1.max(t.v[1].1).max(t.v[2].1).ceil().min((h-1) as f32);
+        if max_y < 0.0 || min_y >= h { continue; }
+        for bin in &mut bins[min_y / BAND_ROWS..=max_y as usize / BAND_ROWS] {
+            bin.push(i as u32);
+        }
+    }
+    bins
+}
+
+// Franjas de BAND_ROWS filas; con la feature `parallel` cada franja va a un hilo de rayon
+pub fn rasterize(fb: &mut Frame, tris: &[ScreenTri], params: &RasterParams) {
+    let (w, h) = (fb.w, fb.h);
+    let chunk = BAND_ROWS * w;
+    let bins = bin_triangles(tris, h);
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        fb.color.par_chunks_mut(chunk)
+            .zip(fb.depth.par_chunks_mut(chunk))
+            .zip(bins.par_iter())
+            .enumerate()
+            .for_each(|(i, ((color, depth), bin))| {
+                let y0 = i * BAND_ROWS;
+                raster_band(&mut Band { w, y0, y1: (y0 + BAND_ROWS).min(h), color, depth }, tris, bin, params);
+            });
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        let bands = fb.color.chunks_mut(chunk).zip(fb.depth.chunks_mut(chunk)).zip(&bins);
+        for (i, ((color, depth), bin)) in bands.enumerate() {
+            let y0 = i * BAND_ROWS;
+            raster_band(&mut Band { w, y0, y1: (y0 + BAND_ROWS).min(h), color, depth }, tris, bin, params);
+        }
+    }
+}
+
+/* ==== HUD: texto bitmap 5x7 ==== */
+// Cada glifo son 7 filas; los 5 bits bajos de cada fila, de izquierda (bit 4) a derecha
+fn glyph(c: char) -> [u8;7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E,0x11,0x13,0x15,0x19,0x11,0x0E],
+        '1' => [0x04,0x0C,0x04,0x04,0x04,0x04,0x0E],
+        '2' => [0x0E,0x11,0x01,0x02,0x04,0x08,0x1F],
+        '3' => [0x1F,0x02,0x04,0x02,0x01,0x11,0x0E],
+        '4' => [0x02,0x06,0x0A,0x12,0x1F,0x02,0x02],
+        '5' => [0x1F,0x10,0x1E,0x01,0x01,0x11,0x0E],
+        '6' => [0x06,0x08,0x10,0x1E,0x11,0x11,0x0E],
+        '7' => [0x1F,0x01,0x02,0x04,0x08,0x08,0x08],
+        '8' => [0x0E,0x11,0x11,0x0E,0x11,0x11,0x0E],
+        '9' => [0x0E,0x11,0x11,0x0F,0x01,0x02,0x0C],
+        'A' => [0x0E,0x11,0x11,0x1F,0x11,0x11,0x11],
+        'B' => [0x1E,0x11,0x11,0x1E,0x11,0x11,0x1E],
+        'C' => [0x0E,0x11,0x10,0x10,0x10,0x11,0x0E],
+        'D' => [0x1C,0x12,0x11,0x11,0x11,0x12,0x1C],
+        'E' => [0x1F,0x10,0x10,0x1E,0x10,0x10,0x1F],
+        'F' => [0x1F,0x10,0x10,0x1E,0x10,0x10,0x10],
+        'G' => [0x0E,0x11,0x10,0x17,0x11,0x11,0x0F],
+        'H' => [0x11,0x11,0x11,0x1F,0x11,0x11,0x11],
+        'I' => [0x0E,0x04,0x04,0x04,0x04,0x04,0x0E],
+        'J' => [0x07,0x02,0x02,0x02,0x02,0x12,0x0C],
+        'K' => [0x11,0x12,0x14,0x18,0x14,0x12,0x11],
+        'L' => [0x10,0x10,0x10,0x10,0x10,0x10,0x1F],
+        'M' => [0x11,0x1B,0x15,0x15,0x11,0x11,0x11],
+        'N' => [0x11,0x11,0x19,0x15,0x13,0x11,0x11],
+        'O' => [0x0E,0x11,0x11,0x11,0x11,0x11,0x0E],
+        'P' => [0x1E,0x11,0x11,0x1E,0x10,0x10,0x10],
+        'Q' => [0x0E,0x11,0x11,0x11,0x15,0x12,0x0D],
+        'R' => [0x1E,0x11,0x11,0x1E,0x14,0x12,0x11],
+        'S' => [0x0F,0x10,0x10,0x0E,0x01,0x01,0x1E],
+        'T' => [0x1F,0x04,0x04,0x04,0x04,0x04,0x04],
+        'U' => [0x11,0x11,0x11,0x11,0x11,0x11,0x0E],
+        'V' => [0x11,0x11,0x11,0x11,0x11,0x0A,0x04],
+        'W' => [0x11,0x11,0x11,0x15,0x15,0x15,0x0A],
+        'X' => [0x11,0x11,0x0A,0x04,0x0A,0x11,0x11],
+        'Y' => [0x11,0x11,0x11,0x0A,0x04,0x04,0x04],
+        'Z' => [0x1F,0x01,0x02,0x04,0x08,0x10,0x1F],
+        ':' => [0x00,0x0C,0x0C,0x00,0x0C,0x0C,0x00],
+        '.' => [0x00,0x00,0x00,0x00,0x00,0x0C,0x0C],
+        '-' => [0x00,0x00,0x00,0x1F,0x00,0x00,0x00],
+        '/' => [0x00,0x01,0x02,0x04,0x08,0x10,0x00],
+        _   => [0x00; 7], // espacio y desconocidos
+    }
+}
+
+const HUD_SCALE: usize = 2; // píxeles de pantalla por punto del glifo
+
+// Escribe directo en `frame.color` (sin z-buffer), con sombra de 1 punto para contraste
+pub fn draw_text(fb: &mut Frame, x: usize, y: usize, text: &str, rgb: u32) {
+    for (dx, dy, col) in [(HUD_SCALE, HUD_SCALE, 0x000000), (0, 0, rgb)] {
+        for (i, c) in text.chars().enumerate() {
+            let gx = x + dx + i * 6 * HUD_SCALE;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for bit in 0..5 {
+                    if bits & (0x10 >> bit) == 0 { continue; }
+                    for sy in 0..HUD_SCALE {
+                        for sx in 0..HUD_SCALE {
+                            let (px, py) = (gx + bit*HUD_SCALE + sx, y + dy + row*HUD_SCALE + sy);
+                            if px < fb.w && py < fb.h { fb.color[py*fb.w + px] = col; }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Una línea por entrada, arriba a la izquierda
+pub fn draw_hud(fb: &mut Frame, lines: &[String]) {
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(fb, 8, 8 + i * 10 * HUD_SCALE, line, 0xE0E0E0);
+    }
+}
+
+/* ==== Overlays: líneas y ejes ==== */
+// Bresenham sin z-buffer (overlay dibujado al final)
+pub fn draw_line(fb: &mut Frame, (x0, y0): (i32,i32), (x1, y1): (i32,i32), rgb: u32) {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+    let (mut x, mut y, mut err) = (x0, y0, dx + dy);
+    loop {
+        if x >= 0 && y >= 0 && (x as usize) < fb.w && (y as usize) < fb.h {
+            fb.color[y as usize * fb.w + x as usize] = rgb;
+        }
+        if x == x1 && y == y1 { break; }
+        let e2 = 2 * err;
+        if e2 >= dy { err += dy; x += sx; }
+        if e2 <= dx { err += dx; y += sy; }
+    }
+}
+
+const GIZMO_LEN: f32 = 35.0;    // px
+const GIZMO_MARGIN: f32 = 55.0; // px desde la esquina inferior derecha
+
+// Ejes X (rojo), Y (verde), Z (azul) girados con la misma rotación que el modelo
+pub fn draw_axis_gizmo(fb: &mut Frame, angle_y: f32, angle_x: f32) {
+    let rot = Mat4::rotation_x(angle_x).mul(&Mat4::rotation_y(angle_y));
+    let (ox, oy) = (fb.w as f32 - GIZMO_MARGIN, fb.h as f32 - GIZMO_MARGIN);
+    let mut axes = [
+        (rot.transform_point(Vec3(1.0, 0.0, 0.0)), 0xFF4040, "X"),
+        (rot.transform_point(Vec3(0.0, 1.0, 0.0)), 0x40FF40, "Y"),
+        (rot.transform_point(Vec3(0.0, 0.0, 1.0)), 0x4080FF, "Z"),
+    ];
+    // del más lejano al más cercano (z de cámara mayor = más lejos)
+    axes.sort_by(|a, b| b.0.2.total_cmp(&a.0.2));
+    for (Vec3(x, y, _), rgb, label) in axes {
+        let (tx, ty) = (ox + x * GIZMO_LEN, oy - y * GIZMO_LEN);
+        draw_line(fb, (ox as i32, oy as i32), (tx as i32, ty as i32), rgb);
+        let (lx, ly) = (ox + x * (GIZMO_LEN + 8.0) - 5.0, oy - y * (GIZMO_LEN + 8.0) - 7.0);
+        draw_text(fb, lx.max(0.0) as usize, ly.max(0.0) as usize, label, rgb);
+    }
+}
+
+// Línea con z-test contra el z-buffer; a, b: (x,y,z_cam) en pantalla.
+// Se recorta al rectángulo de pantalla (Liang–Barsky) antes de recorrerla.
+pub fn draw_line_z(fb: &mut Frame, a: (f32,f32,f32), b: (f32,f32,f32), rgb: u32) {
+    let (dx, dy, dz) = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    let (xmax, ymax) = ((fb.w - 1) as f32, (fb.h - 1) as f32);
+    for (p, q) in [(-dx, a.0), (dx, xmax - a.0), (-dy, a.1), (dy, ymax - a.1)] {
+        if p == 0.0 {
+            if q < 0.0 { return; }
+        } else {
+            let r = q / p;
+            if p < 0.0 { t0 = t0.max(r); } else { t1 = t1.min(r); }
+        }
+    }
+    if t0 > t1 { return; }
+
+    let (x0, y0, z0) = (a.0 + dx*t0, a.1 + dy*t0, a.2 + dz*t0);
+    let (x1, y1, z1) = (a.0 + dx*t1, a.1 + dy*t1, a.2 + dz*t1);
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as usize;
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let (x, y) = ((x0 + (x1-x0)*t).round() as usize, (y0 + (y1-y0)*t).round() as usize);
+        let z = z0 + (z1-z0)*t;
+        let idx = y*fb.w + x;
+        if z < fb.depth[idx] {
+            fb.depth[idx] = z;
+            fb.color[idx] = rgb;
+        }
+    }
+}
+
+// Segmento ya en espacio cámara: recorte contra near y proyección
+pub fn draw_segment_cam(fb: &mut Frame, proj: &Mat4, near: f32, a: Vec3, b: Vec3, rgb: u32) {
+    let (a_in, b_in) = (a.2 >= near, b.2 >= near);
+    if !a_in && !b_in { return; }
+    let clip = |inside: Vec3, outside: Vec3| lerp3(inside, outside, (near - inside.2) / (outside.2 - inside.2));
+    let (a, b) = if !a_in { (clip(b, a), b) } else if !b_in { (a, clip(a, b)) } else { (a, b) };
+    let size = (fb.w, fb.h);
+    let to_screen = |p: Vec3| { let (x, y) = ndc_to_screen(proj.transform_point(p), size); (x, y, p.2) };
+    draw_line_z(fb, to_screen(a), to_screen(b), rgb);
+}
+
+const GRID_COLOR: u32 = 0x34343C;
+const GRID_AXIS_COLOR: u32 = 0x5A5A66;
+
+// Rejilla en el plano XZ (y = 0) del espacio del modelo, una línea por unidad
+// en [-extent, extent]; pasa por la misma matriz de modelo que la malla y respeta el z-buffer.
+pub fn draw_grid(fb: &mut Frame, cam: &Camera, model: &Mat4, extent: i32) {
+    let (view, proj) = cam.matrices(aspect((fb.w, fb.h)));
+    let model_view = view.mul(model);
+    let to_cam = |x: f32, z: f32| model_view.transform_point(Vec3(x, 0.0, z));
+    let e = extent as f32;
+    for i in -extent..=extent {
+        let (k, rgb) = (i as f32, if i == 0 { GRID_AXIS_COLOR } else { GRID_COLOR });
+        draw_segment_cam(fb, &proj, cam.near, to_cam(k, -e), to_cam(k, e), rgb); // paralela a Z
+        draw_segment_cam(fb, &proj, cam.near, to_cam(-e, k), to_cam(e, k), rgb); // paralela a X
+    }
+}
+
+/* ==== Escena: proyección + raster de toda la malla ==== */
+pub const DEFAULT_YAW: f32 = 0.6;
+pub const DEFAULT_PITCH: f32 = 0.0;
+pub const DEFAULT_FOV: f32 = 60.0;
+pub const DEFAULT_CAM_DIST: f32 = 3.0;
+pub const DEFAULT_FIT_MARGIN: f32 = 0.1;
+pub const DEFAULT_BG: u32 = 0x101014;
+pub const DEFAULT_GRADIENT_TOP: u32 = 0x34343F; // arriba del degradado --gradient-bg (abajo, --bg)
+pub const DEFAULT_NEAR: f32 = 0.01;  // planos de recorte en z de cámara (--near/--far)
+pub const DEFAULT_FAR: f32 = 1000.0;
+pub const MIN_CAM_DIST: f32 = 1.5;      // no atravesar el modelo
+pub const MIN_FOV: f32 = 20.0;
+pub const MAX_FOV: f32 = 120.0;
+
+// Cámara orbital alrededor del origen de la escena; el bucle la modifica con los métodos
+// y el render la lee tal cual
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub angle_y: f32,   // yaw
+    pub angle_x: f32,   // pitch
+    pub fov_deg: f32,
+    pub cam_dist: f32,
+    pub pan: (f32,f32), // desplazamiento en x/y de cámara
+    pub ortho: bool,
+    pub near: f32,
+    pub far: f32,
+    pub home_dist: f32, // distancia a la que vuelve `reset` (la de encuadre al cargar)
+}
+
+// La de la CLI sin opciones
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            angle_y: DEFAULT_YAW,
+            angle_x: DEFAULT_PITCH,
+            fov_deg: DEFAULT_FOV,
+            cam_dist: DEFAULT_CAM_DIST,
+            pan: (0.0, 0.0),
+            ortho: false,
+            near: DEFAULT_NEAR,
+            far: DEFAULT_FAR,
+            home_dist: DEFAULT_CAM_DIST,
+        }
+    }
+}
+
+impl Camera {
+    pub fn orbit(&mut self, dx: f32, dy: f32) {
+        self.angle_y += dx;
+        self.angle_x += dy;
+    }
+
+    // Positivo aleja; nunca más cerca que MIN_CAM_DIST
+    pub fn dolly(&mut self, delta: f32) {
+        self.cam_dist = (self.cam_dist + delta).max(MIN_CAM_DIST);
+    }
+
+    // Zoom óptico: cambia el fov sin mover la cámara, dentro de [MIN_FOV, MAX_FOV]
+    pub fn zoom_fov(&mut self, delta: f32) {
+        self.fov_deg = (self.fov_deg + delta).clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Pose inicial: las constantes por defecto de la CLI y la distancia de encuadre;
+    // proyección y planos de recorte no cambian
+    pub fn reset(&mut self) {
+        self.angle_y = DEFAULT_YAW;
+        self.angle_x = DEFAULT_PITCH;
+        self.fov_deg = DEFAULT_FOV;
+        self.cam_dist = self.home_dist;
+        self.pan = (0.0, 0.0);
+    }
+
+    // proyección * vista para un frame de proporción `aspect` (ancho / alto)
+    pub fn view_proj_matrix(&self, aspect: f32) -> Mat4 {
+        let (view, proj) = self.matrices(aspect);
+        proj.mul(&view)
+    }
+
+    // (vista, proyección); la ortográfica usa la escala equivalente a la perspectiva a cam_dist,
+    // así W/S sigue haciendo zoom
+    pub fn matrices(&self, aspect: f32) -> (Mat4, Mat4) {
+        let view = view_matrix(self.angle_y, self.angle_x, self.cam_dist, self.pan);
+        let proj = if self.ortho {
+            Mat4::orthographic(focal_length(self.fov_deg) / self.cam_dist, aspect)
+        } else {
+            Mat4::perspective(self.fov_deg, aspect, self.near, self.far)
+        };
+        (view, proj)
+    }
+}
+
+// Opciones de raster independientes de la cámara
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderOptions {
+    pub cull_backfaces: bool, // off: se pintan las dos caras (mallas con winding inconsistente)
+    pub lighting: bool,       // difusa (Lambert) con la luz fija en espacio cámara
+    pub specular: bool,       // brillo de Phong encima de la difusa
+    pub shininess: f32,
+    pub bilinear: bool,       // filtrado bilineal de la textura
+    pub stats: bool,          // medir cuánto tarda cada etapa (--stats)
+    pub group_colors: bool,   // un color de la paleta por grupo `o`/`g` (tapa textura y colores)
+    pub wireframe: bool,      // aristas oscurecidas sobre el relleno
+    pub affine: bool,         // interpolación lineal en pantalla aunque haya perspectiva (texture swim)
+    pub show_normals: bool,   // un segmento por (v, vn) a lo largo de la normal
+    pub matcap: bool,         // color según la normal, sin luz (tapa textura y colores)
+    pub solo: Option<u32>,    // solo los triángulos de este grupo (índice en `Mesh::groups`)
+    pub points: bool,         // nube de puntos: un bloque por vértice en lugar de los triángulos
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            cull_backfaces: true, lighting: true, specular: false, shininess: DEFAULT_SHININESS,
+            bilinear: false, stats: false, group_colors: false, wireframe: false,
+            affine: false, show_normals: false, matcap: false,
+            solo: None, points: false,
+        }
+    }
+}
+
+// Tiempo por etapa del render (solo se mide con `RenderOptions::stats`)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StageTimes {
+    pub project: Duration,   // vértices a pantalla
+    pub triangles: Duration, // recorte, shading y culling -> ScreenTri
+    pub raster: Duration,
+}
+
+impl std::ops::AddAssign for StageTimes {
+    fn add_assign(&mut self, o: StageTimes) {
+        self.project += o.project;
+        self.triangles += o.triangles;
+        self.raster += o.raster;
+    }
+}
+
+// Distancia a la que la esfera envolvente de `bounds` (centrada en el origen de la escena)
+// ocupa la vista menos `margin` (fracción) en el eje más estrecho del fov
+pub fn fit_camera((lo, hi): (Vec3, Vec3), fov_deg: f32, aspect: f32, margin: f32) -> f32 {
+    let radius = (0.5 * (hi - lo).length()).max(1e-6);
+    let tan_v = 1.0 / focal_length(fov_deg); // con el fov acotado, como la proyección
+    let tan_half = tan_v.min(tan_v * aspect) * (1.0 - margin);
+    (radius / tan_half.atan().sin()).max(MIN_CAM_DIST)
+}
+
+// fit_camera para varias mallas a la vez, con sus cajas llevadas a la escena por `model`.
+// Las mallas sin vértices no cuentan (su caja es el origen), como en scene_bounds.
+pub fn fit_scene(meshes: &[Mesh], model: &Mat4, fov_deg: f32, aspect: f32, margin: f32) -> f32 {
+    let corners: Vec<Vec3> = meshes.iter()
+        .filter(|m| !m.positions.is_empty())
+        .flat_map(|m| [m.bounds.0, m.bounds.1])
+        .map(|p| model.transform_point(p))
+        .collect();
+    fit_camera(aabb(&corners), fov_deg, aspect, margin)
+}
+
+// true si la caja queda entera delante de near, detrás de far o fuera de un mismo lado del frustum.
+// Las pruebas x/y se hacen en clip (-w <= x,y <= w): son lineales, así que valen aunque
+// algún vértice quede detrás de la cámara.
+pub fn aabb_outside_view((lo, hi): (Vec3, Vec3), model_view: &Mat4, proj: &Mat4, (near, far): (f32, f32)) -> bool {
+    let mut out = [true; 6]; // near, far, x<-w, x>w, y<-w, y>w
+    for i in 0..8 {
+        let corner = Vec3(
+            if i & 1 == 0 { lo.0 } else { hi.0 },
+            if i & 2 == 0 { lo.1 } else { hi.1 },
+            if i & 4 == 0 { lo.2 } else { hi.2 },
+        );
+        let pc = model_view.transform_point(corner);
+        let [x, y, _, w] = proj.transform_homogeneous(pc);
+        out[0] &= pc.2 < near;
+        out[1] &= pc.2 > far;
+        out[2] &= x < -w;
+        out[3] &= x > w;
+        out[4] &= y < -w;
+        out[5] &= y > w;
+    }
+    out.iter().any(|&o| o)
+}
+
+// Paleta de colores planos: una entrada por archivo cargado (se repite si hay más)
+pub const MESH_COLORS: [u32; 6] = [0x808080, 0xC08040, 0x4090C0, 0x70B050, 0xB060B0, 0xC0C050];
+
+// Dibuja los triángulos opacos de `mesh` sobre `fb`; `model` lleva sus posiciones al espacio de la
+// escena. Con `texture`, los triángulos cuyos tres vértices traen `vt` se texturizan.
+// Los transparentes (material con d < 1) se devuelven sin pintar: van con draw_transparent
+// cuando toda la escena opaca ya está en el z-buffer.
+pub fn render_mesh<'a>(
+    fb: &mut Frame,
+    mesh: &Mesh,
+    model: &Mat4,
+    cam: &Camera,
+    opts: &RenderOptions,
+    rgb: u32,
+    texture: Option<&'a Texture>,
+) -> (StageTimes, Vec<ScreenTri<'a>>) {
+    let mut times = StageTimes::default();
+    // sin --stats no se llama a Instant::now
+    let mut clock = opts.stats.then(Instant::now);
+    let mut lap = || clock.as_mut().map_or(Duration::ZERO, |t| {
+        let now = Instant::now();
+        let dt = now - *t;
+        *t = now;
+        dt
+    });
+
+    let (view, proj) = cam.matrices(aspect((fb.w, fb.h)));
+    let model_view = view.mul(model);
+    if !mesh.positions.is_empty() && aabb_outside_view(mesh.bounds, &model_view, &proj, (cam.near, cam.far)) { return (times, Vec::new()); }
+
+    // Proyección + depths (z_cam); lo mismo que Camera::view_proj_matrix, sin rehacer las matrices
+    let view_proj = proj.mul(&view);
+    let (screen_pts, depths) = if cam.ortho {
+        project_orthographic_to_screen(&mesh.positions, model, &view_proj, (fb.w, fb.h))
+    } else {
+        project_perspective_to_screen(&mesh.positions, model, &view_proj, (fb.w, fb.h))
+    };
+    times.project = lap();
+    if opts.points {
+        draw_points(fb, mesh, &screen_pts, &depths, cam, rgb);
+        times.raster = lap();
+        return (times, Vec::new());
+    }
+    let to_screen = |pc: Vec3| {
+        let (x, y) = ndc_to_screen(proj.transform_point(pc), (fb.w, fb.h));
+        (x, y, pc.2)
+    };
+    let params = RasterParams::new(cam, opts);
+    // Iluminación y matcap: posiciones en espacio cámara por vértice; normales de `vn` si el triángulo
+    // las trae todas, si no la de la cara (orientada hacia la cámara)
+    let shaded = opts.lighting || opts.matcap; // ambos necesitan posición y normal por píxel
+    let cam_pts: Vec<Vec3> = if shaded {
+        mesh.positions.iter().map(|&p| model_view.transform_point(p)).collect()
+    } else {
+        Vec::new()
+    };
+    let tri_normals = |corners: &[(u32, Option<u32>, Option<u32>);3]| -> [Vec3;3] {
+        match corners.map(|c| c.2) {
+            [Some(a), Some(b), Some(c)] => [a, b, c].map(|n| model_view.transform_dir(mesh.normals[n as usize])),
+            _ => {
+                let [p0, p1, p2] = corners.map(|c| cam_pts[c.0 as usize]);
+                let n = (p1 - p0).cross(p2 - p0).normalize();
+                let n = if n.dot(p0) > 0.0 { n.scale(-1.0) } else { n };
+                [n; 3]
+            }
+        }
+    };
+
+    // prioridad: textura (si el triángulo tiene vt) > color por vértice > Kd del material > color plano
+    let has_colors = !mesh.colors.is_empty();
+    let material = |t: usize| mesh.tri_material.get(t).copied().flatten().map(|m| &mesh.materials[m as usize]);
+    let phong_of = |t: usize| material(t).map_or(Phong::default(), Phong::of);
+    let alpha_of = |t: usize| material(t).and_then(|m| m.d).map_or(1.0, |d| d.clamp(0.0, 1.0));
+    let vertex_color = |i: usize| if has_colors { mesh.colors[i] } else { Vec3(0.0, 0.0, 0.0) };
+    let vertex_uv = |vt: Option<u32>| vt.map_or(Vec2(0.0, 0.0), |t| mesh.texcoords[t as usize]);
+    let shade_of = |t: usize, textured: bool, c: [Vec3;3], uv: [Vec2;3]| match texture {
+        _ if opts.group_colors => Shade::Flat(MESH_COLORS[mesh.tri_group[t] as usize % MESH_COLORS.len()]),
+        Some(tex) if textured => Shade::Texture(tex, uv),
+        _ if has_colors => Shade::Vertex(c),
+        _ => Shade::Flat(material(t).and_then(|m| m.kd).map_or(rgb, pack_rgb)),
+    };
+
+    let mut tris = Vec::with_capacity(mesh.indices.len() / 3);
+    for (t, tri) in mesh.indices.chunks_exact(3).enumerate() {
+        if opts.solo.is_some_and(|g| mesh.tri_group[t] != g) { continue; }
+        let corners = [tri[0], tri[1], tri[2]];
+        let idx = corners.map(|(v, _, _)| v as usize);
+        let textured = corners.iter().all(|c| c.1.is_some());
+        // entero más allá de far: fuera
+        if idx.iter().all(|&i| depths[i] > cam.far) { continue; }
+        let near_in = idx.iter().filter(|&&i| depths[i] > cam.near).count();
+
+        match near_in {
+            // todo detrás del plano near
+            0 => {}
+            3 => {
+                let v = idx.map(|i| (screen_pts[i].0, screen_pts[i].1, depths[i]));
+                let surface = shaded.then(|| Surface { p: idx.map(|i| cam_pts[i]), n: tri_normals(&corners), phong: phong_of(t) });
+                let shade = shade_of(t, textured, idx.map(vertex_color), corners.map(|c| vertex_uv(c.1)));
+                push_triangle(&mut tris, v, shade, surface, alpha_of(t), opts.cull_backfaces);
+            }
+            // cruza el plano near: recortar en espacio cámara y proyectar lo que queda
+            _ => {
+                let normals = if shaded { tri_normals(&corners) } else { [Vec3::default(); 3] };
+                let cam_tri: [CamVertex;3] = std::array::from_fn(|k| {
+                    let (v, vt, _) = corners[k];
+                    CamVertex {
+                        p: model_view.transform_point(mesh.positions[v as usize]),
+                        n: normals[k],
+                        color: vertex_color(v as usize),
+                        uv: vertex_uv(vt),
+                    }
+                });
+                for clipped in clip_triangle_near(cam_tri, cam.near) {
+                    let shade = shade_of(t, textured, clipped.map(|cv| cv.color), clipped.map(|cv| cv.uv));
+                    let surface = shaded.then(|| Surface { p: clipped.map(|cv| cv.p), n: clipped.map(|cv| cv.n), phong: phong_of(t) });
+                    push_triangle(&mut tris, clipped.map(|cv| to_screen(cv.p)), shade, surface, alpha_of(t), opts.cull_backfaces);
+                }
+            }
+        }
+    }
+
+    let transparent: Vec<ScreenTri> = tris.extract_if(.., |t| t.alpha < 1.0).collect();
+    times.triangles = lap();
+
+    rasterize(fb, &tris, &params);
+    // polilíneas `l`: después del relleno para respetar su z-buffer
+    for &(a, b) in &mesh.lines {
+        let [a, b] = [a, b].map(|i| model_view.transform_point(mesh.positions[i as usize]));
+        draw_segment_cam(fb, &proj, cam.near, a, b, rgb);
+    }
+    if opts.show_normals { draw_normals(fb, mesh, &model_view, &proj, cam.near); }
+    times.raster = lap();
+    (times, transparent)
+}
+
+// Transparentes de toda la escena (lo que devuelve render_mesh de cada malla), tras lo opaco:
+// de atrás hacia delante por la z media, mezclados sin escribir profundidad
+pub fn draw_transparent(fb: &mut Frame, tris: &mut [ScreenTri], cam: &Camera, opts: &RenderOptions) {
+    if tris.is_empty() { return; }
+    let mean_z = |t: &ScreenTri| (t.v[0].2 + t.v[1].2 + t.v[2].2) / 3.0;
+    tris.sort_by(|a, b| mean_z(b).total_cmp(&mean_z(a)));
+    // bin_triangles conserva el orden dentro de cada franja, así que el orden de mezcla se mantiene
+    rasterize(fb, tris, &RasterParams::new(cam, opts));
+}
+
+// Lo que se pinta: las mallas, la matriz que las lleva a la escena y la textura (si hay)
+#[derive(Clone, Copy)]
+pub struct Scene<'a> {
+    pub meshes: &'a [Mesh],
+    pub model: Mat4,
+    pub texture: Option<&'a Texture>,
+}
+
+// Fondo, colores y extras de la escena (lo que no es cámara ni RenderOptions)
+#[derive(Clone, Copy, Debug)]
+pub struct SceneStyle {
+    pub bg: u32,
+    pub gradient_bg: bool,  // degradado de DEFAULT_GRADIENT_TOP arriba a `bg` abajo
+    pub color: Option<u32>, // relleno de todas las mallas; None: uno de MESH_COLORS por malla
+    pub shared_color: bool, // todas con el color de la primera (frames de una animación)
+    pub grid: bool,         // rejilla en el plano XZ
+    pub grid_extent: i32,
+    pub msaa: bool,         // supermuestreo 2x2
+}
+
+impl Default for SceneStyle {
+    fn default() -> Self {
+        Self { bg: DEFAULT_BG, gradient_bg: false, color: None, shared_color: false, grid: false, grid_extent: 10, msaa: false }
+    }
+}
+
+// Fondo + mallas + rejilla (lo que lleva z-buffer); los overlays 2D van aparte
+fn render_scene(frame: &mut Frame, scene: &Scene, cam: &Camera, opts: &RenderOptions, style: &SceneStyle) -> StageTimes {
+    if style.gradient_bg { frame.clear_gradient(DEFAULT_GRADIENT_TOP, style.bg); } else { frame.clear(style.bg); }
+    let mut times = StageTimes::default();
+    let mut transparent = Vec::new();
+    for (i, mesh) in scene.meshes.iter().enumerate() {
+        let slot = if style.shared_color { 0 } else { i };
+        let rgb = style.color.unwrap_or(MESH_COLORS[slot % MESH_COLORS.len()]); // 0xRRGGBB
+        let (t, glass) = render_mesh(frame, mesh, &scene.model, cam, opts, rgb, scene.texture);
+        times += t;
+        transparent.extend(glass);
+    }
+    if style.grid { draw_grid(frame, cam, &scene.model, style.grid_extent); }
+    // lo transparente de todas las mallas, cuando ya está todo lo opaco (rejilla incluida)
+    draw_transparent(frame, &mut transparent, cam, opts);
+    times
+}
+
+// Renderiza la escena en `frame`; con MSAA se rasteriza al doble en `hires` (que se
+// reutiliza entre frames) y se reduce a `frame`
+pub fn render_into(
+    frame: &mut Frame,
+    hires: &mut Frame,
+    scene: &Scene,
+    cam: &Camera,
+    opts: &RenderOptions,
+    style: &SceneStyle,
+) -> StageTimes {
+    if style.msaa {
+        hires.resize(2*frame.w, 2*frame.h);
+        let times = render_scene(hires, scene, cam, opts, style);
+        hires.downsample_2x_into(frame);
+        times
+    } else {
+        render_scene(frame, scene, cam, opts, style)
+    }
+}
+
+// Frame nuevo de `size` con la escena, sin overlays (para tests y usos como biblioteca)
+pub fn render_to_frame(scene: &Scene, cam: &Camera, opts: &RenderOptions, style: &SceneStyle, size: (usize, usize)) -> Frame {
+    let mut frame = Frame::new(size.0, size.1);
+    render_into(&mut frame, &mut Frame::new(0, 0), scene, cam, opts, style);
+    frame
+}
+
+// Largo en unidades del modelo normalizado (bbox de tamaño 2): se ve sin tapar la malla
+const NORMAL_LEN: f32 = 0.06;
+const NORMAL_COLOR: u32 = 0x40E0FF;
+
+// Normales de depuración: cada par (v, vn) distinto una vez, con z-test como la malla
+pub fn draw_normals(fb: &mut Frame, mesh: &Mesh, model_view: &Mat4, proj: &Mat4, near: f32) {
+    let mut seen: HashSet<(u32, u32)> = HashSet::new();
+    for &(v, _, vn) in &mesh.indices {
+        let Some(vn) = vn else { continue };
+        if !seen.insert((v, vn)) { continue; }
+        let p = model_view.transform_point(mesh.positions[v as usize]);
+        let n = model_view.transform_dir(mesh.normals[vn as usize]).normalize();
+        draw_segment_cam(fb, proj, near, p, p + n.scale(NORMAL_LEN), NORMAL_COLOR);
+    }
+}
+
+// Radio del bloque de un punto a la distancia cam_dist; más cerca crece (cam_dist / zc)
+// hasta POINT_MAX_RADIUS y más lejos se queda en un píxel
+const POINT_SIZE: f32 = 1.0;
+const POINT_MAX_RADIUS: i32 = 4;
+
+// Nube de puntos: cada vértice como un cuadrado de (2r+1)² píxeles, todos con la z del
+// punto, así los cercanos tapan a los lejanos. Color por vértice si la malla lo trae.
+pub fn draw_points(fb: &mut Frame, mesh: &Mesh, screen_pts: &[(f32, f32)], depths: &[f32], cam: &Camera, rgb: u32) {
+    let (w, h) = (fb.w, fb.h);
+    let mut band = Band { w, y0: 0, y1: h, color: &mut fb.color, depth: &mut fb.depth };
+    for (i, (&(x, y), &z)) in screen_pts.iter().zip(depths).enumerate() {
+        if z <= cam.near || z > cam.far { continue; }
+        let r = ((POINT_SIZE * cam.cam_dist / z) as i32).clamp(0, POINT_MAX_RADIUS);
+        let rgb = mesh.colors.get(i).map_or(rgb, |&c| pack_rgb(c));
+        let (cx, cy) = (x.round() as i32, y.round() as i32);
+        for py in cy - r..=cy + r {
+            for px in cx - r..=cx + r { band.put_pixel_z(px, py, z, rgb); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::center_and_scale_to_unit;
+
+    #[test]
+    fn degradado_de_arriba_abajo() {
+        let (top, bottom) = (0x102030, 0xF0E0D0);
+        let mut frame = Frame::new(4, 5);
+        frame.depth.fill(1.0); // lo de un frame anterior
+        frame.clear_gradient(top, bottom);
+        assert!(frame.color[..4].iter().all(|&c| c == top));
+        assert!(frame.color[16..].iter().all(|&c| c == bottom));
+        assert!(frame.depth.iter().all(|&z| z == f32::INFINITY));
+    }
+
+    #[test]
+    fn encuadre_ignora_mallas_vacias() {
+        let cube = Mesh { positions: vec![Vec3(19.0, -1.0, -1.0), Vec3(21.0, 1.0, 1.0)], ..Mesh::default() };
+        let cube = Mesh { bounds: aabb(&cube.positions), ..cube };
+        let empty = Mesh::default(); // solo comentarios: caja en el origen
+        let solo = [cube.clone()];
+        let both = [cube, empty];
+        let model = center_and_scale_to_unit(&both);
+        assert_eq!(model, center_and_scale_to_unit(&solo));
+        assert_eq!(fit_scene(&both, &model, 60.0, 1.0, 0.1), fit_scene(&solo, &model, 60.0, 1.0, 0.1));
+    }
+}
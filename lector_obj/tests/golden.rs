@@ -0,0 +1,37 @@
+// El raster contra las imágenes de referencia de golden/ (las mismas que `--compare`)
+use std::io::Cursor;
+use std::path::Path;
+
+use lector_obj::export::{count_diff_pixels, read_ppm};
+use lector_obj::math::{aspect, center_and_scale_to_unit};
+use lector_obj::mesh::{parse_obj, LoadOptions};
+use lector_obj::render::{fit_scene, render_to_frame, Camera, RenderOptions, Scene, SceneStyle, DEFAULT_FIT_MARGIN};
+
+const TOLERANCE: u8 = 2; // la de --compare por defecto
+
+// Lo mismo que `lector_obj <obj> --pitch 0.4 --compare golden/<ppm>`
+fn assert_matches_golden(obj: &str, ppm: &str) {
+    let mut mesh = parse_obj(Cursor::new(obj), &LoadOptions::default()).unwrap();
+    if mesh.normals.is_empty() { mesh.compute_vertex_normals(); }
+    let meshes = [mesh];
+    let (w, h, expected) = read_ppm(&Path::new(env!("CARGO_MANIFEST_DIR")).join("golden").join(ppm)).unwrap();
+
+    let model = center_and_scale_to_unit(&meshes);
+    let mut camera = Camera { angle_x: 0.4, ..Camera::default() };
+    camera.cam_dist = fit_scene(&meshes, &model, camera.fov_deg, aspect((w, h)), DEFAULT_FIT_MARGIN);
+    let scene = Scene { meshes: &meshes, model, texture: None };
+    let frame = render_to_frame(&scene, &camera, &RenderOptions::default(), &SceneStyle::default(), (w, h));
+
+    let diff = count_diff_pixels(&frame.color, &expected, TOLERANCE);
+    assert_eq!(diff, 0, "{diff} píxeles distintos de golden/{ppm}");
+}
+
+#[test]
+fn cubo() {
+    assert_matches_golden(include_str!("../golden/cube.obj"), "cube.ppm");
+}
+
+#[test]
+fn formas_de_vertice_mezcladas() {
+    assert_matches_golden(include_str!("../golden/mixed.obj"), "mixed.ppm");
+}